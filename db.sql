@@ -13,14 +13,19 @@ CREATE TABLE IF NOT EXISTS users (
   username             VARCHAR(50) UNIQUE,
   email                VARCHAR(255) UNIQUE NOT NULL,
   password_hash        VARCHAR(255),
-  role                 VARCHAR(10) NOT NULL DEFAULT 'user',
+  role                 VARCHAR(32) NOT NULL DEFAULT 'user', -- roles.name (ดูข้อ 18)
   profile_picture_url  TEXT DEFAULT 'assets/user.png',
   is_email_verified    BOOLEAN NOT NULL DEFAULT FALSE,
   oauth_provider       VARCHAR(20),
   oauth_id             VARCHAR(255),
+  webauthn_user_id     UUID UNIQUE,          -- user handle ของ passkey (สุ่มครั้งแรกที่ลงทะเบียน)
+  failed_login_attempts INTEGER NOT NULL DEFAULT 0,
+  last_failed_login_at TIMESTAMPTZ,
+  locked_until         TIMESTAMPTZ,          -- ล็อกชั่วคราวจาก login ผิดติดกัน
+  locale               VARCHAR(5),           -- ภาษาของอีเมล ('en' | 'th'), NULL = ดูจาก Accept-Language
+  deletion_scheduled_at TIMESTAMPTZ,         -- ขอลบบัญชีแล้ว ลบจริงเมื่อถึงเวลานี้
   created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-  updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-  CONSTRAINT chk_role CHECK (role IN ('user','admin'))
+  updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
 CREATE INDEX IF NOT EXISTS idx_users_email
@@ -29,6 +34,18 @@ CREATE INDEX IF NOT EXISTS idx_users_email
 CREATE INDEX IF NOT EXISTS idx_users_oauth
   ON users(oauth_provider, oauth_id);
 
+-- สำหรับ DB ที่สร้างไว้ก่อนแล้ว
+ALTER TABLE users ADD COLUMN IF NOT EXISTS webauthn_user_id UUID UNIQUE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_failed_login_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale VARCHAR(5);
+-- DELETE /api/users/me: จะถูกลบจริงเมื่อถึงเวลานี้ (login ก่อนถึงเวลา = ยกเลิก) NULL = ไม่ได้ขอลบ
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_deletion
+  ON users(deletion_scheduled_at) WHERE deletion_scheduled_at IS NOT NULL;
+
 
 -- -------------------------------------------------------
 -- 2) VERIFICATION CODES (ยืนยันอีเมล)
@@ -36,13 +53,39 @@ CREATE INDEX IF NOT EXISTS idx_users_oauth
 CREATE TABLE IF NOT EXISTS verification_codes (
   id          SERIAL PRIMARY KEY,
   user_id     INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-  code        VARCHAR(6) NOT NULL,
+  code_hash   VARCHAR(64) NOT NULL,         -- sha256 ของรหัส 6 หลัก (ไม่เก็บรหัสจริง)
   expires_at  TIMESTAMPTZ NOT NULL
 );
 
+-- DB เดิมเก็บ code เป็น plaintext -> hash รหัสที่ยังค้างอยู่แล้วลบคอลัมน์เดิมทิ้ง
+ALTER TABLE verification_codes ADD COLUMN IF NOT EXISTS code_hash VARCHAR(64);
+DO $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM information_schema.columns
+             WHERE table_name = 'verification_codes' AND column_name = 'code') THEN
+    UPDATE verification_codes SET code_hash = encode(sha256(convert_to(code, 'UTF8')), 'hex')
+    WHERE code_hash IS NULL;
+    ALTER TABLE verification_codes DROP COLUMN code;
+  END IF;
+END $$;
+ALTER TABLE verification_codes ALTER COLUMN code_hash SET NOT NULL;
+
 CREATE INDEX IF NOT EXISTS idx_verif_user_exp
   ON verification_codes(user_id, expires_at);
 
+-- ใส่รหัสผิดครบ VERIFY_CODE_MAX_ATTEMPTS -> รหัสนั้นใช้ไม่ได้
+ALTER TABLE verification_codes ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+
+-- log การส่งรหัส ใช้คุม cooldown + จำนวนครั้งต่อวันต่ออีเมล (เก็บแค่ 1 วัน)
+CREATE TABLE IF NOT EXISTS verification_code_sends (
+  id       SERIAL PRIMARY KEY,
+  email    VARCHAR(255) NOT NULL,
+  sent_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_verif_sends_email
+  ON verification_code_sends(email, sent_at);
+
 
 -- -------------------------------------------------------
 -- 3) PASSWORD RESET TOKENS (ลืมรหัสผ่าน)
@@ -50,12 +93,27 @@ CREATE INDEX IF NOT EXISTS idx_verif_user_exp
 CREATE TABLE IF NOT EXISTS password_reset_tokens (
   id         SERIAL PRIMARY KEY,
   user_id    INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-  token      VARCHAR(255) UNIQUE NOT NULL,
+  token_hash VARCHAR(64) NOT NULL,          -- sha256 ของ token ในลิงก์ (ไม่เก็บ token จริง)
   expires_at TIMESTAMPTZ NOT NULL,
   is_used    BOOLEAN NOT NULL DEFAULT FALSE,
   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- DB เดิมเก็บ token เป็น plaintext -> hash token ที่ยังค้างอยู่ (ลิงก์ในอีเมลยังใช้ได้) แล้วลบคอลัมน์เดิมทิ้ง
+ALTER TABLE password_reset_tokens ADD COLUMN IF NOT EXISTS token_hash VARCHAR(64);
+DO $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM information_schema.columns
+             WHERE table_name = 'password_reset_tokens' AND column_name = 'token') THEN
+    UPDATE password_reset_tokens SET token_hash = encode(sha256(convert_to(token, 'UTF8')), 'hex')
+    WHERE token_hash IS NULL;
+    ALTER TABLE password_reset_tokens DROP COLUMN token;
+  END IF;
+END $$;
+ALTER TABLE password_reset_tokens ALTER COLUMN token_hash SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_password_reset_token_hash
+  ON password_reset_tokens(token_hash);
+
 CREATE INDEX IF NOT EXISTS idx_reset_user_exp
   ON password_reset_tokens(user_id, is_used, expires_at);
 
@@ -102,10 +160,344 @@ CREATE TABLE IF NOT EXISTS api_clients (
   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- aud ใน access token = name ของ client ที่ login
+-- accepts_audiences = client อื่น (name) ที่ยอมรับ token ของเขาได้ เช่น web สองตัวที่ใช้ token ร่วมกัน
+ALTER TABLE api_clients ADD COLUMN IF NOT EXISTS accepts_audiences TEXT[] NOT NULL DEFAULT '{}';
+
 CREATE INDEX IF NOT EXISTS idx_api_clients_active
   ON api_clients(is_active);
 
 
+-- -------------------------------------------------------
+-- 7) REFRESH TOKENS (หมุนใหม่ทุกครั้งที่ใช้ /api/auth/refresh)
+--    family_id = สาย token ที่มาจาก login ครั้งเดียวกัน
+--    ถ้า token ที่ถูกหมุนไปแล้วถูกใช้ซ้ำ จะปิดทั้ง family
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+  id             SERIAL PRIMARY KEY,
+  user_id        INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  family_id      VARCHAR(64) NOT NULL,
+  token_hash     VARCHAR(64) UNIQUE NOT NULL, -- sha256 ของ token (ไม่เก็บตัวจริง)
+  expires_at     TIMESTAMPTZ NOT NULL,
+  revoked_at     TIMESTAMPTZ,
+  revoked_reason VARCHAR(20),                 -- rotated | logout | reuse
+  replaced_by    INTEGER REFERENCES refresh_tokens(id) ON DELETE SET NULL,
+  auth_time      TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- เวลาที่ login จริงของ family (ส่งต่อตอนหมุน)
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS auth_time TIMESTAMPTZ NOT NULL DEFAULT NOW();
+-- client ที่ login (หมุน token ได้เฉพาะผ่าน client เดิม) NULL = token ก่อนมี column นี้
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS api_client_id INTEGER REFERENCES api_clients(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_refresh_family
+  ON refresh_tokens(family_id);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_user
+  ON refresh_tokens(user_id, revoked_at);
+
+
+-- -------------------------------------------------------
+-- 8) REVOKED TOKENS (jti denylist ที่ mw_jwt_auth เช็ค)
+--    jti != NULL  -> revoke token ใบเดียว (logout)
+--    jti  = NULL  -> revoke ทุก token ของ user ที่ออกก่อน revoked_at
+--                    (reset password / admin kick)
+--    แถวที่หมดอายุจะถูกลบโดย background job
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+  id          SERIAL PRIMARY KEY,
+  jti         VARCHAR(64) UNIQUE,
+  user_id     INTEGER REFERENCES users(id) ON DELETE CASCADE,
+  reason      VARCHAR(30),
+  revoked_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at  TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_revoked_user
+  ON revoked_tokens(user_id, revoked_at);
+
+CREATE INDEX IF NOT EXISTS idx_revoked_exp
+  ON revoked_tokens(expires_at);
+
+
+-- -------------------------------------------------------
+-- 9) TWO-FACTOR AUTH (TOTP + recovery codes)
+--    ใช้กับ /api/auth/2fa
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS user_mfa (
+  user_id         INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  totp_secret     VARCHAR(64) NOT NULL,     -- base32
+  is_enabled      BOOLEAN NOT NULL DEFAULT FALSE,
+  last_used_step  BIGINT,                   -- กันใช้รหัสเดิมซ้ำ
+  enabled_at      TIMESTAMPTZ,
+  created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS mfa_recovery_codes (
+  id         SERIAL PRIMARY KEY,
+  user_id    INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  code_hash  VARCHAR(64) NOT NULL,          -- sha256 ของ code
+  used_at    TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_mfa_recovery_user
+  ON mfa_recovery_codes(user_id, used_at);
+
+-- mfa_token ที่ออกหลังผ่านขั้นแรก: ใช้ได้ครั้งเดียว และต้องยืนยันจาก client (x-api-key) เดียวกับที่ login
+CREATE TABLE IF NOT EXISTS mfa_challenges (
+  jti            VARCHAR(64) PRIMARY KEY,
+  user_id        INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  api_client_id  INTEGER NOT NULL REFERENCES api_clients(id) ON DELETE CASCADE,
+  expires_at     TIMESTAMPTZ NOT NULL,
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+
+-- -------------------------------------------------------
+-- 10) PASSKEYS (WebAuthn)
+--     ใช้กับ /api/auth/passkeys
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS user_credentials (
+  id             SERIAL PRIMARY KEY,
+  user_id        INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  credential_id  TEXT UNIQUE NOT NULL,      -- hex ของ credential id
+  passkey        JSONB NOT NULL,            -- public key + sign counter (webauthn-rs Passkey)
+  name           VARCHAR(100),
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_used_at   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_credentials_user
+  ON user_credentials(user_id);
+
+-- state ระหว่าง start -> finish ของแต่ละ ceremony (ใช้ได้ครั้งเดียว อายุ 5 นาที)
+CREATE TABLE IF NOT EXISTS webauthn_challenges (
+  id          VARCHAR(64) PRIMARY KEY,
+  user_id     INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  kind        VARCHAR(20) NOT NULL,         -- register | login
+  state       JSONB NOT NULL,
+  expires_at  TIMESTAMPTZ NOT NULL
+);
+
+
+-- -------------------------------------------------------
+-- 11) OAUTH AUTHORIZATION-CODE FLOW (PKCE)
+--     ใช้กับ GET /api/auth/oauth/google -> /callback -> POST /api/auth/oauth/exchange
+-- -------------------------------------------------------
+-- state ระหว่าง redirect ไป provider -> callback (ใช้ได้ครั้งเดียว อายุ 10 นาที)
+CREATE TABLE IF NOT EXISTS oauth_states (
+  state_hash     VARCHAR(64) PRIMARY KEY,   -- sha256 ของ state
+  provider       VARCHAR(20) NOT NULL,
+  code_verifier  VARCHAR(128) NOT NULL,     -- PKCE verifier (ส่งให้ provider ตอนแลก code)
+  redirect_uri   TEXT NOT NULL,             -- URL ของ frontend ที่จะพากลับไป
+  link_user_id   INTEGER REFERENCES users(id) ON DELETE CASCADE, -- != NULL = ผูกบัญชีเพิ่ม ไม่ใช่ login
+  expires_at     TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE oauth_states ADD COLUMN IF NOT EXISTS link_user_id INTEGER REFERENCES users(id) ON DELETE CASCADE;
+
+-- one-time login code ที่ส่งกลับไปให้ frontend แลกเป็น token (อายุ 1 นาที)
+CREATE TABLE IF NOT EXISTS oauth_login_codes (
+  code_hash   VARCHAR(64) PRIMARY KEY,      -- sha256 ของ code
+  user_id     INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  expires_at  TIMESTAMPTZ NOT NULL
+);
+
+
+-- -------------------------------------------------------
+-- 12) USER IDENTITIES (บัญชี OAuth/OIDC ที่ผูกกับ user ได้หลายอัน)
+--     ใช้กับ /api/users/me/identities
+--     users.oauth_provider/oauth_id เหลือไว้เพื่อ compatibility (= provider แรกที่ผูก)
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS user_identities (
+  id             SERIAL PRIMARY KEY,
+  user_id        INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  provider       VARCHAR(20) NOT NULL,
+  subject        VARCHAR(255) NOT NULL,    -- sub / user id ฝั่ง provider
+  email          VARCHAR(255),
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_login_at  TIMESTAMPTZ,
+  CONSTRAINT uq_user_identities UNIQUE (provider, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_identities_user
+  ON user_identities(user_id);
+
+-- ย้ายข้อมูลจาก users.oauth_provider/oauth_id เดิม
+INSERT INTO user_identities (user_id, provider, subject, email)
+SELECT id, oauth_provider, oauth_id, email
+FROM users
+WHERE oauth_provider IS NOT NULL AND oauth_provider <> 'local' AND oauth_id IS NOT NULL
+ON CONFLICT (provider, subject) DO NOTHING;
+
+
+-- -------------------------------------------------------
+-- 13) MAGIC LINKS (login ผ่านลิงก์ในอีเมล ไม่ต้องใช้รหัสผ่าน)
+--     ใช้กับ /api/auth/magic-link -> /api/auth/magic-link/consume
+--     ใช้ได้ครั้งเดียว และต้องแลกจาก client (x-api-key) เดียวกับที่ขอ
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS magic_link_tokens (
+  token_hash     VARCHAR(64) PRIMARY KEY,   -- sha256 ของ token
+  user_id        INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  api_client_id  INTEGER NOT NULL REFERENCES api_clients(id) ON DELETE CASCADE,
+  expires_at     TIMESTAMPTZ NOT NULL,
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_magic_link_tokens_user
+  ON magic_link_tokens(user_id);
+
+
+-- -------------------------------------------------------
+-- 14) EMAIL CHANGE (เปลี่ยนอีเมล ต้องยืนยันรหัสที่ส่งไปอีเมลใหม่ก่อน)
+--     ใช้กับ /api/users/me/email -> /api/users/me/email/confirm
+--     ค้างได้ทีละ 1 คำขอต่อ user (ขอใหม่ = ทับของเดิม)
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS email_change_requests (
+  user_id     INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  new_email   VARCHAR(255) NOT NULL,
+  code_hash   VARCHAR(64) NOT NULL,         -- sha256 ของรหัส 6 หลัก
+  attempts    INTEGER NOT NULL DEFAULT 0,   -- ผิดครบ EMAIL_CODE_MAX_ATTEMPTS -> ต้องขอใหม่
+  expires_at  TIMESTAMPTZ NOT NULL,
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE email_change_requests ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+
+
+-- -------------------------------------------------------
+-- 15) SESSIONS (1 แถวต่อการ login 1 ครั้ง = 1 refresh token family)
+--     access token มี sid ชี้มาที่นี่ revoke แล้ว mw_jwt_auth จะไม่รับ
+--     ดู/ปิดได้ที่ /api/users/me/sessions และ /api/users/:id/sessions (admin)
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS sessions (
+  id             SERIAL PRIMARY KEY,
+  user_id        INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  family_id      VARCHAR(64) NOT NULL UNIQUE,   -- refresh_tokens.family_id
+  api_client_id  INTEGER REFERENCES api_clients(id) ON DELETE SET NULL,
+  client_name    VARCHAR(100),                  -- เก็บชื่อไว้เผื่อ client ถูกลบ
+  user_agent     VARCHAR(512),
+  ip             VARCHAR(64),
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_seen_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  revoked_at     TIMESTAMPTZ,
+  revoked_reason VARCHAR(20),                   -- logout | revoked | reuse | kicked | password_change | impersonation
+  impersonator_id INTEGER REFERENCES users(id) ON DELETE CASCADE, -- admin ที่สวมรอย (ไม่มี refresh token)
+  expires_at     TIMESTAMPTZ                    -- ใช้กับ session สวมรอยเท่านั้น
+);
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS impersonator_id INTEGER REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user
+  ON sessions(user_id, revoked_at);
+
+
+-- -------------------------------------------------------
+-- 16) IMPERSONATIONS (audit: admin สวมรอยเป็น user)
+--     1 แถวต่อครั้ง เริ่ม = INSERT, หยุด = ended_at (ไม่มี ended_at และเลย expires_at = หมดอายุเอง)
+--     ไม่ผูก FK กับ users เพื่อให้ประวัติอยู่ต่อแม้ลบ user ไปแล้ว
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS impersonations (
+  id          SERIAL PRIMARY KEY,
+  session_id  INTEGER NOT NULL,             -- sessions.id ของ token สวมรอย
+  admin_id    INTEGER NOT NULL,
+  user_id     INTEGER NOT NULL,
+  reason      TEXT NOT NULL,
+  ip          VARCHAR(64),
+  user_agent  VARCHAR(512),
+  started_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at  TIMESTAMPTZ NOT NULL,
+  ended_at    TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonations_user
+  ON impersonations(user_id, started_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_impersonations_admin
+  ON impersonations(admin_id, started_at DESC);
+
+
+-- -------------------------------------------------------
+-- 17) PERSONAL ACCESS TOKENS (token อายุยาวสำหรับ script / QA automation)
+--     ใช้เป็น Bearer pat_... แทน JWT ได้ เก็บเฉพาะ hash (โชว์ตัวจริงครั้งเดียวตอนสร้าง)
+--     scopes: read | write | admin
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+  id            SERIAL PRIMARY KEY,
+  user_id       INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name          VARCHAR(100) NOT NULL,
+  token_hash    VARCHAR(64) NOT NULL UNIQUE,  -- sha256 ของ token
+  token_prefix  VARCHAR(12) NOT NULL,         -- pat_xxxxxxxx ไว้ให้ user จำได้ว่าอันไหน
+  scopes        TEXT[] NOT NULL,
+  expires_at    TIMESTAMPTZ NOT NULL,
+  last_used_at  TIMESTAMPTZ,
+  revoked_at    TIMESTAMPTZ,
+  created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_pat_user
+  ON personal_access_tokens(user_id, revoked_at);
+
+
+-- -------------------------------------------------------
+-- 18) ROLES / PERMISSIONS (RBAC)
+--     users.role -> roles.name, สิทธิ์จริงดูจาก role_permissions
+--     permission ถูกเช็คในโค้ด (mw_require_permission) จึง seed ไว้ที่นี่เท่านั้น
+--     role สร้าง/แก้ได้ผ่าน /api/admin/roles
+-- -------------------------------------------------------
+CREATE TABLE IF NOT EXISTS roles (
+  name         VARCHAR(32) PRIMARY KEY,
+  description  TEXT,
+  is_system    BOOLEAN NOT NULL DEFAULT FALSE,   -- user / admin ลบไม่ได้
+  created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+  name         VARCHAR(64) PRIMARY KEY,
+  description  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+  role_name    VARCHAR(32) NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+  permission   VARCHAR(64) NOT NULL REFERENCES permissions(name) ON DELETE CASCADE,
+  PRIMARY KEY (role_name, permission)
+);
+
+INSERT INTO roles (name, description, is_system)
+VALUES
+  ('user',  'Regular account', TRUE),
+  ('admin', 'Full access', TRUE)
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO permissions (name, description)
+VALUES
+  ('clients:manage',    'Manage API clients (/api/admin/clients)'),
+  ('roles:manage',      'Manage roles and their permissions'),
+  ('users:manage',      'List users, change roles, kick and unlock accounts'),
+  ('sessions:manage',   'View and revoke other users'' sessions'),
+  ('users:impersonate', 'Sign in as another user for support'),
+  ('homepage:write',    'Edit homepage sections'),
+  ('carousel:write',    'Create, edit and delete carousel items')
+ON CONFLICT (name) DO NOTHING;
+
+-- admin ได้ทุก permission เสมอ (รวมที่เพิ่มทีหลัง เมื่อรันสคริปต์ซ้ำ)
+INSERT INTO role_permissions (role_name, permission)
+SELECT 'admin', name FROM permissions
+ON CONFLICT DO NOTHING;
+
+-- สำหรับ DB ที่สร้างไว้ก่อนแล้ว: เปลี่ยน chk_role เป็น FK ไปที่ roles
+ALTER TABLE users DROP CONSTRAINT IF EXISTS chk_role;
+ALTER TABLE users ALTER COLUMN role TYPE VARCHAR(32);
+DO $$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_users_role') THEN
+    ALTER TABLE users ADD CONSTRAINT fk_users_role FOREIGN KEY (role) REFERENCES roles(name);
+  END IF;
+END $$;
+
+
 -- -------------------------------------------------------
 -- (OPTIONAL) SEED ข้อมูลเริ่มต้น
 -- -------------------------------------------------------
@@ -118,6 +510,16 @@ VALUES
 ON CONFLICT (section_name) DO NOTHING;
 
 
+-- ตัวอย่าง role สำหรับทีม marketing (แก้หน้าแรก/carousel ได้ แต่ไม่ใช่ admin)
+INSERT INTO roles (name, description)
+VALUES ('editor', 'Homepage and carousel editing')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_name, permission)
+VALUES ('editor', 'homepage:write'), ('editor', 'carousel:write')
+ON CONFLICT DO NOTHING;
+
+
 -- ตัวอย่าง seed api_clients (เปลี่ยน key ให้ตรงกับ .env / config ฝั่ง client)
 INSERT INTO api_clients (name, api_key)
 VALUES
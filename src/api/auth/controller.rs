@@ -1,11 +1,18 @@
-use axum::{extract::State, Json};
+use axum::{extract::State, http::HeaderMap, Extension, Json};
 use serde_json::json;
 use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
 use super::schema::*;
 use super::service;
 
-pub async fn register(State((db, _)): State<(crate::config::db::DB, crate::config::env::Env)>, Json(body): Json<RegisterBody>) -> Result<Json<serde_json::Value>, AppError> {
-    service::register(&db, body).await?;
+// ภาษาที่ browser ส่งมา ใช้เลือก template อีเมลเมื่อ user ยังไม่ได้ตั้ง locale
+fn accept_language(headers: &HeaderMap) -> Option<&str> {
+    headers.get(axum::http::header::ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok())
+}
+
+pub async fn register(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, headers: HeaderMap, Json(body): Json<RegisterBody>) -> Result<Json<serde_json::Value>, AppError> {
+    service::register(&db, &env, accept_language(&headers), body).await?;
     Ok(Json(json!({ "ok": true })))
 }
 
@@ -14,30 +21,50 @@ pub async fn verify_code(State((db, _)): State<(crate::config::db::DB, crate::co
     Ok(Json(json!({ "ok": true })))
 }
 
-pub async fn complete_profile(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Json(body): Json<CompleteProfileBody>) -> Result<Json<serde_json::Value>, AppError> {
-    let out = service::complete_profile(&db, &env, body).await?;
+pub async fn resend_code(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, headers: HeaderMap, Json(body): Json<ResendCodeBody>) -> Result<Json<serde_json::Value>, AppError> {
+    let out = service::resend_code(&db, &env, accept_language(&headers), body).await?;
     Ok(Json(json!({ "ok": true, "data": out })))
 }
 
-pub async fn login(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Json(body): Json<LoginBody>) -> Result<Json<serde_json::Value>, AppError> {
-    let out = service::login(&db, &env, body).await?;
+pub async fn complete_profile(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Extension(client): Extension<ApiClient>, device: Device, Json(body): Json<CompleteProfileBody>) -> Result<Json<serde_json::Value>, AppError> {
+    let out = service::complete_profile(&db, &env, &client, &device, body).await?;
     Ok(Json(json!({ "ok": true, "data": out })))
 }
 
-pub async fn logout() -> Json<serde_json::Value> { Json(json!({ "ok": true })) }
+pub async fn login(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Extension(client): Extension<ApiClient>, device: Device, Json(body): Json<LoginBody>) -> Result<Json<serde_json::Value>, AppError> {
+    let out = service::login(&db, &env, &client, &device, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+pub async fn refresh(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Extension(client): Extension<ApiClient>, device: Device, Json(body): Json<RefreshBody>) -> Result<Json<serde_json::Value>, AppError> {
+    let out = service::refresh(&db, &env, &client, &device, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// body เป็น optional เพราะ client เก่ายิง logout มาแบบไม่มี body
+pub async fn logout(State((db, _)): State<(crate::config::db::DB, crate::config::env::Env)>, headers: HeaderMap, body: Option<Json<LogoutBody>>) -> Result<Json<serde_json::Value>, AppError> {
+    let access_token = crate::core::middleware::jwt_auth::bearer_token(&headers);
+    service::logout(&db, access_token, body.map(|Json(b)| b).unwrap_or_default()).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+pub async fn forgot_password(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, headers: HeaderMap, Json(body): Json<ForgotPasswordBody>) -> Result<Json<serde_json::Value>, AppError> {
+    service::forgot_password(&db, &env, accept_language(&headers), body).await?;
+    Ok(Json(json!({ "ok": true })))
+}
 
-pub async fn forgot_password(State((db, _)): State<(crate::config::db::DB, crate::config::env::Env)>, Json(body): Json<ForgotPasswordBody>) -> Result<Json<serde_json::Value>, AppError> {
-    service::forgot_password(&db, body).await?;
+pub async fn reset_password(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, headers: HeaderMap, Json(body): Json<ResetPasswordBody>) -> Result<Json<serde_json::Value>, AppError> {
+    service::reset_password(&db, &env, accept_language(&headers), body).await?;
     Ok(Json(json!({ "ok": true })))
 }
 
-pub async fn reset_password(State((db, _)): State<(crate::config::db::DB, crate::config::env::Env)>, Json(body): Json<ResetPasswordBody>) -> Result<Json<serde_json::Value>, AppError> {
-    service::reset_password(&db, body).await?;
+pub async fn magic_link(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Extension(client): Extension<ApiClient>, headers: HeaderMap, Json(body): Json<MagicLinkBody>) -> Result<Json<serde_json::Value>, AppError> {
+    service::send_magic_link(&db, &env, &client, accept_language(&headers), body).await?;
     Ok(Json(json!({ "ok": true })))
 }
 
-pub async fn google_oauth(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Json(body): Json<GoogleOAuthBody>) -> Result<Json<serde_json::Value>, AppError> {
-    let out = service::google_oauth(&db, &env, body).await?;
+pub async fn consume_magic_link(State((db, env)): State<(crate::config::db::DB, crate::config::env::Env)>, Extension(client): Extension<ApiClient>, device: Device, Json(body): Json<ConsumeMagicLinkBody>) -> Result<Json<serde_json::Value>, AppError> {
+    let out = service::consume_magic_link(&db, &env, &client, &device, body).await?;
     Ok(Json(json!({ "ok": true, "data": out })))
 }
 
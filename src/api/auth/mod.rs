@@ -1,4 +1,6 @@
 pub mod controller;
+pub mod reauth;
+pub mod verification;
 pub mod routes;
 pub mod schema;
 pub mod service;
\ No newline at end of file
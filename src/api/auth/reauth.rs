@@ -0,0 +1,47 @@
+use serde_json::json;
+use sqlx::Row;
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+use crate::core::utils::{jwt, password};
+
+use super::service;
+
+/// ยืนยันตัวตนซ้ำก่อนทำงานสำคัญ (ผูก/ลบบัญชี, เปลี่ยนรหัสผ่าน/อีเมล ฯลฯ)
+///
+/// - มีรหัสผ่าน -> ต้องส่งรหัสผ่านปัจจุบันมา
+/// - ไม่มีรหัสผ่าน (OAuth/passkey อย่างเดียว) -> ต้อง login มาไม่เกิน REAUTH_MAX_AGE_SECS
+pub async fn require(db: &DB, env: &Env, user: &AuthUser, password: Option<&str>) -> Result<(), AppError> {
+    let row = sqlx::query("SELECT password_hash FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    let password_hash: Option<String> = row.get("password_hash");
+
+    if let Some(hash) = password_hash {
+        let Some(pw) = password.filter(|p| !p.is_empty()) else {
+            return Err(AppError::unauthorized("REAUTH_REQUIRED", "Current password is required")
+                .with_details(json!({ "method": "password" })));
+        };
+        // นับรวมกับ login ผิด (ไม่งั้นคนที่ขโมย access token ไปจะใช้ตรงนี้เดารหัสผ่านได้ไม่จำกัด)
+        if let Some(retry_after) = service::lockout_remaining(db, user.id).await? {
+            return Err(AppError::account_locked(retry_after));
+        }
+        if !password::verify_password(pw, &hash).await? {
+            if let Some(retry_after) = service::record_failed_login(db, env, user.id).await? {
+                return Err(AppError::account_locked(retry_after));
+            }
+            return Err(AppError::unauthorized("INVALID_CREDENTIALS", "Invalid credentials"));
+        }
+        service::clear_failed_logins(db, user.id).await?;
+        return Ok(());
+    }
+
+    if jwt::now_ts().saturating_sub(user.auth_time) > env.reauth_max_age_secs {
+        return Err(AppError::unauthorized("REAUTH_REQUIRED", "Please sign in again to continue")
+            .with_details(json!({ "method": "recent_login", "max_age": env.reauth_max_age_secs })));
+    }
+    Ok(())
+}
@@ -10,16 +10,19 @@ pub fn routes(db: DB, env: Env) -> Router {
         // Auth Flow
         .route("/register", post(controller::register))
         .route("/verify-code", post(controller::verify_code))
+        .route("/resend-code", post(controller::resend_code))
         .route("/complete-profile", post(controller::complete_profile))
         .route("/login", post(controller::login))
+        .route("/refresh", post(controller::refresh))
         .route("/logout", post(controller::logout))
         
         // Password Reset
         .route("/forgot-password", post(controller::forgot_password))
         .route("/reset-password", post(controller::reset_password))
-        
-        // OAuth
-        .route("/oauth/google", post(controller::google_oauth))
+
+        // Magic Link (passwordless)
+        .route("/magic-link", post(controller::magic_link))
+        .route("/magic-link/consume", post(controller::consume_magic_link))
         
         // User Info
         .route(
@@ -27,5 +30,11 @@ pub fn routes(db: DB, env: Env) -> Router {
             get(controller::me)
                 .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth)),
         )
-        .with_state((db, env))
+        .with_state((db.clone(), env.clone()))
+        // Two-factor (TOTP)
+        .nest("/2fa", crate::api::mfa::routes::routes(db.clone(), env.clone()))
+        // Passkey (WebAuthn)
+        .nest("/passkeys", crate::api::passkeys::routes::routes(db.clone(), env.clone()))
+        // OAuth / OIDC (POST /oauth/:provider, /oauth/exchange)
+        .nest("/oauth", crate::api::oauth::routes::routes(db, env))
 }
\ No newline at end of file
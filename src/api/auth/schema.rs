@@ -13,6 +13,18 @@ pub struct VerifyCodeBody {
     pub code: String,
 }
 
+// ขอรหัสยืนยันใหม่
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResendCodeBody {
+    pub email: String,
+}
+
+// ขอรหัสใหม่ได้อีกครั้งในอีกกี่วินาที
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResendCodeResponse {
+    pub retry_after: i64,
+}
+
 // สำหรับหน้าตั้งชื่อและรหัสผ่าน (form.html)
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompleteProfileBody {
@@ -27,16 +39,6 @@ pub struct LoginBody {
     pub password: String,
 }
 
-// รองรับ OAuth จาก Frontend (camelCase)
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GoogleOAuthBody {
-    pub email: String,
-    pub oauth_id: String,
-    pub username: Option<String>,
-    pub picture_url: Option<String>,
-}
-
 // สำหรับลืมรหัสผ่าน
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ForgotPasswordBody {
@@ -51,12 +53,51 @@ pub struct ResetPasswordBody {
     pub new_password: String,
 }
 
+// ขอ magic link (login ไม่ใช้รหัสผ่าน)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkBody {
+    pub email: String,
+}
+
+// แลก token จากลิงก์ในอีเมลเป็น access/refresh token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsumeMagicLinkBody {
+    pub token: String,
+}
+
+// สำหรับขอ access token ใหม่ (refresh token จะถูกหมุนทุกครั้ง)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshBody {
+    #[serde(alias = "refresh_token")]
+    pub refresh_token: String,
+}
+
+// logout: ส่ง refresh token มาด้วยเพื่อปิดทั้ง family (ไม่ส่งก็ได้)
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutBody {
+    #[serde(alias = "refresh_token")]
+    pub refresh_token: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthResponse {
+    // access token (อายุสั้น) ใช้ชื่อ token เหมือนเดิมเพื่อไม่ให้ client เดิมพัง
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: usize,
     pub user: UserResponse,
 }
 
+// /login คืน token ตามปกติ หรือ challenge ถ้า user เปิด 2FA ไว้
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Tokens(AuthResponse),
+    MfaRequired(crate::api::mfa::schema::MfaChallenge),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserResponse {
     pub id: i32,
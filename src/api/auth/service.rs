@@ -1,9 +1,17 @@
+use crate::api::identities::service as identities_service;
+use crate::api::mfa::service as mfa_service;
+use crate::api::sessions::service as sessions_service;
+use crate::api::oauth::schema::OAuthIdentity;
 use crate::config::{db::DB, env::Env};
 use crate::core::errors::AppError;
-use crate::core::utils::jwt;
+use crate::core::mail::{self, templates::{Locale, Template}};
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::utils::{jwt, password, password_policy, revocation, token_hash};
 use super::schema::*;
-use bcrypt::{hash, verify, DEFAULT_COST};
+use super::verification;
 use rand::{Rng, distributions::Alphanumeric};
+use sqlx::Row;
 
 #[derive(sqlx::FromRow)]
 struct UserRow {
@@ -14,6 +22,8 @@ struct UserRow {
     role: String,
     profile_picture_url: Option<String>,
     is_email_verified: bool,
+    #[sqlx(default)]
+    locale: Option<String>,
 }
 
 // ✅ เพิ่มฟังก์ชันนี้สำหรับ Route /me
@@ -35,119 +45,467 @@ pub async fn get_me(db: &DB, user_id: i32) -> Result<UserResponse, AppError> {
     })
 }
 
-pub async fn register(db: &DB, body: RegisterBody) -> Result<(), AppError> {
+// --- Access / Refresh Tokens ---
+
+fn to_user_response(u: UserRow) -> UserResponse {
+    UserResponse { id: u.id, email: u.email, username: u.username, role: u.role, profile_picture_url: u.profile_picture_url, is_email_verified: u.is_email_verified }
+}
+
+/// สร้าง refresh token ใหม่ใน family ที่กำหนด (เก็บเฉพาะ hash ลง DB) คืน (id, token ตัวจริง)
+async fn insert_refresh_token<'e, E>(exec: E, env: &Env, client: &ApiClient, user_id: i32, family_id: &str, auth_time: usize) -> Result<(i32, String), AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let token = token_hash::create_random_token();
+    let row = sqlx::query(
+        r#"
+        INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at, auth_time, api_client_id)
+        VALUES ($1, $2, $3, NOW() + make_interval(secs => $4), to_timestamp($5), $6)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(family_id)
+    .bind(token_hash::hash_token(&token))
+    .bind(jwt::refresh_ttl(env) as f64)
+    .bind(auth_time as f64)
+    .bind(client.id)
+    .fetch_one(exec)
+    .await?;
+
+    Ok((row.get("id"), token))
+}
+
+/// login สำเร็จ: เปิด session ใหม่ + ออก access token + refresh token (family ใหม่) ให้ client ที่ login
+async fn issue_tokens(db: &DB, env: &Env, client: &ApiClient, device: &Device, u: UserRow) -> Result<AuthResponse, AppError> {
+    let family_id = token_hash::create_random_token();
+    let auth_time = jwt::now_ts();
+    let mut tx = db.pool.begin().await?;
+    let (_, refresh_token) = insert_refresh_token(&mut *tx, env, client, u.id, &family_id, auth_time).await?;
+    let sid = sessions_service::open(&mut *tx, u.id, &family_id, client, device).await?;
+    // login ระหว่างรอลบบัญชี = ยกเลิกการลบ
+    let cancelled = sqlx::query("UPDATE users SET deletion_scheduled_at = NULL WHERE id = $1 AND deletion_scheduled_at IS NOT NULL")
+        .bind(u.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() > 0;
+    tx.commit().await?;
+    if cancelled {
+        tracing::info!("user {} signed in, account deletion cancelled", u.id);
+    }
+    let token = jwt::sign(u.id, u.email.clone(), u.role.clone(), auth_time, &client.name, sid, env).map_err(|_| AppError::internal("Token sign error"))?;
+
+    Ok(AuthResponse { token, refresh_token, expires_in: jwt::access_ttl(env), user: to_user_response(u) })
+}
+
+/// ให้ module อื่น (เช่น 2FA) ออก token ให้ user ที่ยืนยันตัวตนครบแล้ว
+pub async fn issue_for_user(db: &DB, env: &Env, client: &ApiClient, device: &Device, user_id: i32) -> Result<AuthResponse, AppError> {
+    let u = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    issue_tokens(db, env, client, device, u).await
+}
+
+/// เหมือน issue_for_user แต่ผ่านแค่ขั้นแรก (เช่น OAuth) -> เปิด 2FA ไว้ได้ challenge แทน token
+pub async fn issue_or_challenge_for_user(db: &DB, env: &Env, client: &ApiClient, device: &Device, user_id: i32) -> Result<LoginResponse, AppError> {
+    let u = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    issue_or_challenge(db, env, client, device, u).await
+}
+
+/// ผ่านขั้นแรกแล้ว (รหัสผ่าน / magic link / ...) เปิด 2FA ไว้ -> ยังไม่ให้ token จริง ต้องไปยืนยันที่ /api/auth/2fa/verify
+async fn issue_or_challenge(db: &DB, env: &Env, client: &ApiClient, device: &Device, u: UserRow) -> Result<LoginResponse, AppError> {
+    if mfa_service::is_enabled(db, u.id).await? {
+        return Ok(LoginResponse::MfaRequired(mfa_service::challenge(db, env, client, u.id).await?));
+    }
+    Ok(LoginResponse::Tokens(issue_tokens(db, env, client, device, u).await?))
+}
+
+/// POST /api/auth/refresh : หมุน refresh token (ใช้ได้ครั้งเดียว)
+/// ถ้า token ที่ถูกหมุนไปแล้วโผล่มาอีก ถือว่าโดนขโมย -> ปิดทั้ง family
+pub async fn refresh(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: RefreshBody) -> Result<AuthResponse, AppError> {
+    let hashed = token_hash::hash_token(body.refresh_token.trim());
+    let mut tx = db.pool.begin().await?;
+
+    // mark ว่าถูกหมุนแล้วแบบ atomic กันสอง request ใช้ token เดียวกันพร้อมกัน
+    // ต้องหมุนผ่าน client เดิมที่ login (token เก่าที่ยังไม่มี client ผูกกับ client นี้ไปเลย)
+    let rotated = sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW(), revoked_reason = 'rotated'
+        WHERE token_hash = $1 AND revoked_at IS NULL AND expires_at > NOW()
+          AND (api_client_id IS NULL OR api_client_id = $2)
+        RETURNING id, user_id, family_id, FLOOR(EXTRACT(EPOCH FROM auth_time))::BIGINT AS auth_time
+        "#,
+    )
+    .bind(&hashed)
+    .bind(client.id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(old) = rotated else {
+        tx.rollback().await?;
+        let existing = sqlx::query("SELECT family_id, revoked_reason FROM refresh_tokens WHERE token_hash = $1")
+            .bind(&hashed)
+            .fetch_optional(&db.pool)
+            .await?;
+
+        if let Some(r) = existing {
+            let reason: Option<String> = r.get("revoked_reason");
+            if reason.as_deref() == Some("rotated") {
+                let family_id: String = r.get("family_id");
+                tracing::warn!("refresh token reuse detected, revoking family {}", family_id);
+                revocation::revoke_family(db, &family_id, "reuse").await?;
+                return Err(AppError::unauthorized("REFRESH_REUSED", "Refresh token reuse detected, please login again"));
+            }
+        }
+        return Err(AppError::unauthorized("REFRESH_INVALID", "Invalid or expired refresh token"));
+    };
+
+    let old_id: i32 = old.get("id");
+    let user_id: i32 = old.get("user_id");
+    let family_id: String = old.get("family_id");
+    // refresh ไม่นับเป็นการ login ใหม่ -> ใช้ auth_time เดิมของ family
+    let auth_time = old.get::<i64, _>("auth_time") as usize;
+
+    let (new_id, refresh_token) = insert_refresh_token(&mut *tx, env, client, user_id, &family_id, auth_time).await?;
+    sqlx::query("UPDATE refresh_tokens SET replaced_by = $2 WHERE id = $1")
+        .bind(old_id)
+        .bind(new_id)
+        .execute(&mut *tx)
+        .await?;
+    let sid = sessions_service::open(&mut *tx, user_id, &family_id, client, device).await?;
+
+    let u = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("REFRESH_INVALID", "Invalid or expired refresh token"))?;
+
+    tx.commit().await?;
+
+    let token = jwt::sign(u.id, u.email.clone(), u.role.clone(), auth_time, &client.name, sid, env).map_err(|_| AppError::internal("Token sign error"))?;
+    Ok(AuthResponse { token, refresh_token, expires_in: jwt::access_ttl(env), user: to_user_response(u) })
+}
+
+/// POST /api/auth/logout : revoke access token ที่ส่งมา + ปิด refresh token family ของ device นี้
+pub async fn logout(db: &DB, access_token: Option<String>, body: LogoutBody) -> Result<(), AppError> {
+    // token หมดอายุ/ปลอม ก็ไม่ต้อง revoke
+    if let Some(claims) = access_token.and_then(|t| jwt::verify(&t).ok()) {
+        revocation::revoke_token(db, &claims, "logout").await?;
+    }
+
+    let Some(refresh_token) = body.refresh_token.filter(|t| !t.trim().is_empty()) else { return Ok(()) };
+
+    let row = sqlx::query("SELECT family_id FROM refresh_tokens WHERE token_hash = $1")
+        .bind(token_hash::hash_token(refresh_token.trim()))
+        .fetch_optional(&db.pool)
+        .await?;
+
+    if let Some(r) = row {
+        let family_id: String = r.get("family_id");
+        revocation::revoke_family(db, &family_id, "logout").await?;
+    }
+    Ok(())
+}
+
+pub async fn register(db: &DB, env: &Env, accept_language: Option<&str>, body: RegisterBody) -> Result<(), AppError> {
     let email = body.email.trim().to_lowercase();
     if email.is_empty() { return Err(AppError::bad_request("Email is required")); }
 
     let user = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1").bind(&email).fetch_optional(&db.pool).await?;
+    let header_locale = accept_language.and_then(Locale::from_accept_language);
 
-    let user_id = if let Some(u) = user {
+    let (user_id, user_locale) = if let Some(u) = user {
         if u.is_email_verified && u.password_hash.is_some() {
              return Err(AppError::conflict("EMAIL_EXISTS", "Email already registered"));
         }
-        u.id
+        (u.id, u.locale)
     } else {
-        let row: (i32,) = sqlx::query_as("INSERT INTO users (email, role, is_email_verified) VALUES ($1, 'user', FALSE) RETURNING id").bind(&email).fetch_one(&db.pool).await?;
-        row.0
+        // จำภาษาจาก Accept-Language ไว้ใช้กับอีเมลฉบับต่อๆ ไป
+        let row: (i32,) = sqlx::query_as("INSERT INTO users (email, role, is_email_verified, locale) VALUES ($1, 'user', FALSE, $2) RETURNING id").bind(&email).bind(header_locale.map(|l| l.as_str())).fetch_one(&db.pool).await?;
+        (row.0, None)
     };
 
-    let code: String = rand::thread_rng().gen_range(100000..999999).to_string();
-    sqlx::query("INSERT INTO verification_codes (user_id, code, expires_at) VALUES ($1, $2, NOW() + INTERVAL '10 minutes')").bind(user_id).bind(&code).execute(&db.pool).await?;
-    println!(">>> [MOCK EMAIL] To: {}, Code: {} <<<", email, code);
+    let (code, _) = verification::issue(db, Some(user_id), &email).await?;
+    let locale = Locale::resolve(user_locale.as_deref(), accept_language);
+    mail::queue(Template::VerificationCode { code: &code, minutes: 10 }.render(&email, locale, &env.app_name));
     Ok(())
 }
 
 pub async fn verify_code(db: &DB, body: VerifyCodeBody) -> Result<(), AppError> {
     let email = body.email.trim().to_lowercase();
     let user = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1").bind(&email).fetch_optional(&db.pool).await?.ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
-    let row = sqlx::query("SELECT id FROM verification_codes WHERE user_id = $1 AND code = $2 AND expires_at > NOW()").bind(user.id).bind(&body.code).fetch_optional(&db.pool).await?;
-    if row.is_none() { return Err(AppError::bad_request("Invalid or expired code")); }
+    verification::check(db, user.id, &body.code).await?.into_result()?;
     sqlx::query("UPDATE users SET is_email_verified = TRUE WHERE id = $1").bind(user.id).execute(&db.pool).await?;
-    sqlx::query("DELETE FROM verification_codes WHERE user_id = $1").bind(user.id).execute(&db.pool).await?;
     Ok(())
 }
 
-pub async fn complete_profile(db: &DB, env: &Env, body: CompleteProfileBody) -> Result<AuthResponse, AppError> {
+/// POST /api/auth/resend-code : ส่งรหัสใหม่ (ติด cooldown / เพดานรายวัน = 429 + retry_after)
+/// อีเมลที่ไม่มีหรือยืนยันแล้วตอบเหมือนส่งสำเร็จ และติด cooldown / เพดานเหมือนกัน ไม่บอกว่ามีในระบบหรือไม่
+pub async fn resend_code(db: &DB, env: &Env, accept_language: Option<&str>, body: ResendCodeBody) -> Result<ResendCodeResponse, AppError> {
     let email = body.email.trim().to_lowercase();
-    if body.password.len() < 6 { return Err(AppError::bad_request("Password too short")); }
-    let pw_hash = hash(body.password, DEFAULT_COST).map_err(|_| AppError::internal("Hash error"))?;
+    if email.is_empty() { return Err(AppError::bad_request("Email is required")); }
+
+    let user = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1").bind(&email).fetch_optional(&db.pool).await?;
+    let user = user.filter(|u| !u.is_email_verified);
+
+    let (code, retry_after) = verification::issue(db, user.as_ref().map(|u| u.id), &email).await?;
+    if let Some(u) = user {
+        let locale = Locale::resolve(u.locale.as_deref(), accept_language);
+        mail::queue(Template::VerificationCode { code: &code, minutes: 10 }.render(&email, locale, &env.app_name));
+    }
+    Ok(ResendCodeResponse { retry_after })
+}
+
+/// POST /api/auth/complete-profile : ตั้ง username/รหัสผ่านครั้งแรกหลังยืนยันอีเมล
+/// เฉพาะบัญชีที่ยังไม่มีรหัสผ่านและไม่ได้ผูก OAuth ไว้ (ไม่งั้นรู้แค่อีเมลก็ตั้งรหัสทับยึดบัญชีได้)
+pub async fn complete_profile(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: CompleteProfileBody) -> Result<LoginResponse, AppError> {
+    let email = body.email.trim().to_lowercase();
+    password_policy::check(&body.password, Some(&email), Some(&body.username))?;
+    let pw_hash = password::hash_password(&body.password).await?;
 
     let u = sqlx::query_as::<_, UserRow>(
-        r#"UPDATE users SET username = $2, password_hash = $3 WHERE email = $1 AND is_email_verified = TRUE RETURNING id, username, email, password_hash, role, profile_picture_url, is_email_verified"#
+        r#"UPDATE users SET username = $2, password_hash = $3
+           WHERE email = $1 AND is_email_verified = TRUE AND password_hash IS NULL
+             AND NOT EXISTS (SELECT 1 FROM user_identities i WHERE i.user_id = users.id)
+           RETURNING id, username, email, password_hash, role, profile_picture_url, is_email_verified"#
     ).bind(&email).bind(&body.username).bind(pw_hash).fetch_optional(&db.pool).await?
     .ok_or_else(|| AppError::unauthorized("NOT_VERIFIED", "User not verified or not found"))?;
 
-    // ✅ แก้ไข: ลบ name ออกจาก sign
-    let token = jwt::sign(u.id, u.email.clone(), u.role.clone(), env).map_err(|_| AppError::internal("Token sign error"))?;
+    issue_or_challenge(db, env, client, device, u).await
+}
 
-    Ok(AuthResponse {
-        token,
-        user: UserResponse { id: u.id, email: u.email, username: u.username, role: u.role, profile_picture_url: u.profile_picture_url, is_email_verified: u.is_email_verified },
-    })
+// --- Account Lockout ---
+
+/// วินาทีที่เหลือก่อนปลดล็อก (None = ไม่ได้ถูกล็อก)
+pub async fn lockout_remaining(db: &DB, user_id: i32) -> Result<Option<i64>, AppError> {
+    let remaining: Option<i64> = sqlx::query_scalar(
+        "SELECT CEIL(EXTRACT(EPOCH FROM (locked_until - NOW())))::BIGINT FROM users WHERE id = $1 AND locked_until > NOW()",
+    )
+    .bind(user_id)
+    .fetch_optional(&db.pool)
+    .await?;
+    Ok(remaining)
+}
+
+/// นับครั้งที่ผิด ถ้าครบ LOGIN_MAX_ATTEMPTS จะล็อก base * 2^(ครั้งที่เกิน) วินาที (ไม่เกิน max)
+/// คืนเวลาที่ถูกล็อก ถ้าครั้งนี้ทำให้โดนล็อก
+pub async fn record_failed_login(db: &DB, env: &Env, user_id: i32) -> Result<Option<i64>, AppError> {
+    let row = sqlx::query(
+        r#"
+        UPDATE users
+        SET failed_login_attempts = failed_login_attempts + 1,
+            last_failed_login_at = NOW(),
+            locked_until = CASE
+                WHEN failed_login_attempts + 1 >= $2
+                THEN NOW() + make_interval(secs => LEAST($3 * POWER(2, LEAST(failed_login_attempts + 1 - $2, 30)), $4))
+                ELSE locked_until
+            END
+        WHERE id = $1
+        RETURNING failed_login_attempts
+        "#,
+    )
+    .bind(user_id)
+    .bind(env.login_max_attempts)
+    .bind(env.login_lockout_secs as f64)
+    .bind(env.login_lockout_max_secs as f64)
+    .fetch_one(&db.pool)
+    .await?;
+
+    let attempts: i32 = row.get("failed_login_attempts");
+    if attempts >= env.login_max_attempts {
+        tracing::warn!("user {} locked after {} failed login attempts", user_id, attempts);
+        return lockout_remaining(db, user_id).await;
+    }
+    Ok(None)
 }
 
-pub async fn login(db: &DB, env: &Env, body: LoginBody) -> Result<AuthResponse, AppError> {
+pub async fn clear_failed_logins(db: &DB, user_id: i32) -> Result<(), AppError> {
+    sqlx::query("UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = $1 AND failed_login_attempts > 0")
+        .bind(user_id)
+        .execute(&db.pool)
+        .await?;
+    Ok(())
+}
+
+/// hash เก่า (bcrypt / parameter เก่า) -> hash ใหม่ตอนที่รู้รหัสผ่านจริง พังก็แค่ log ไม่ให้ login ล้ม
+async fn rehash_password(db: &DB, user_id: i32, old_hash: &str, plain: &str) {
+    let new_hash = match password::hash_password(plain).await {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::warn!("rehash for user {} failed: {}", user_id, e);
+            return;
+        }
+    };
+    // เช็ค hash เดิมด้วย กันทับรหัสผ่านที่เพิ่งถูกเปลี่ยนพร้อมกัน
+    let res = sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1 AND password_hash = $3")
+        .bind(user_id)
+        .bind(new_hash)
+        .bind(old_hash)
+        .execute(&db.pool)
+        .await;
+    if let Err(e) = res {
+        tracing::warn!("rehash for user {} failed: {}", user_id, e);
+    }
+}
+
+pub async fn login(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: LoginBody) -> Result<LoginResponse, AppError> {
     let email = body.email.trim().to_lowercase();
     let u = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1").bind(&email).fetch_optional(&db.pool).await?.ok_or_else(|| AppError::unauthorized("INVALID_CREDENTIALS", "Invalid credentials"))?;
-    let is_valid = match &u.password_hash { Some(h) => verify(&body.password, h).unwrap_or(false), None => false };
-    if !is_valid { return Err(AppError::unauthorized("INVALID_CREDENTIALS", "Invalid credentials")); }
 
-    // ✅ แก้ไข: ลบ name ออกจาก sign
-    let token = jwt::sign(u.id, u.email.clone(), u.role.clone(), env).map_err(|_| AppError::internal("Token sign error"))?;
+    // ถูกล็อกอยู่ -> ไม่ต้องเสียเวลา hash
+    if let Some(retry_after) = lockout_remaining(db, u.id).await? {
+        return Err(AppError::account_locked(retry_after));
+    }
 
-    Ok(AuthResponse {
-        token,
-        user: UserResponse { id: u.id, email: u.email, username: u.username, role: u.role, profile_picture_url: u.profile_picture_url, is_email_verified: u.is_email_verified },
-    })
+    let is_valid = match &u.password_hash { Some(h) => password::verify_password(&body.password, h).await.unwrap_or(false), None => false };
+    if !is_valid {
+        if let Some(retry_after) = record_failed_login(db, env, u.id).await? {
+            return Err(AppError::account_locked(retry_after));
+        }
+        return Err(AppError::unauthorized("INVALID_CREDENTIALS", "Invalid credentials"));
+    }
+    // เปิด 2FA ไว้ -> ล้างตัวนับตอนผ่านขั้นที่สองแทน (ไม่งั้น login ใหม่ก็ล้างตัวนับรหัส 2FA ที่เดาผิดได้)
+    if !mfa_service::is_enabled(db, u.id).await? {
+        clear_failed_logins(db, u.id).await?;
+    }
+    if let Some(h) = u.password_hash.as_deref().filter(|h| password::needs_rehash(h)) {
+        rehash_password(db, u.id, h, &body.password).await;
+    }
+
+    issue_or_challenge(db, env, client, device, u).await
 }
 
-pub async fn google_oauth(db: &DB, env: &Env, body: GoogleOAuthBody) -> Result<AuthResponse, AppError> {
-    let email = body.email.to_lowercase();
-    let provider = "google";
-    let oauth_id = body.oauth_id;
+/// หา/ผูก/สร้าง user จากตัวตนที่ provider ยืนยันแล้ว (ใช้กับทุก provider ใน registry)
+///
+/// ผูกไว้แล้ว -> user เดิม, ยังไม่ผูกแต่อีเมลตรง -> ผูกเพิ่มให้ user นั้น, ไม่เจอเลย -> สร้างใหม่
+pub async fn oauth_login_user(db: &DB, provider: &str, identity: &OAuthIdentity) -> Result<i32, AppError> {
+    if let Some(user_id) = identities_service::find_user(db, provider, identity).await? {
+        sqlx::query("UPDATE users SET profile_picture_url = COALESCE($2, profile_picture_url) WHERE id = $1")
+            .bind(user_id).bind(&identity.picture).execute(&db.pool).await?;
+        return Ok(user_id);
+    }
 
-    let existing_oauth = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE oauth_provider = $1 AND oauth_id = $2").bind(provider).bind(&oauth_id).fetch_optional(&db.pool).await?;
-    let u = if let Some(user) = existing_oauth {
-        sqlx::query_as::<_, UserRow>(r#"UPDATE users SET email=$2, is_email_verified=TRUE, profile_picture_url=COALESCE($3, profile_picture_url), username=COALESCE(username, $4) WHERE id=$1 RETURNING *"#)
-        .bind(user.id).bind(&email).bind(&body.picture_url).bind(&body.username).fetch_one(&db.pool).await?
-    } else {
-        let existing_email = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email=$1").bind(&email).fetch_optional(&db.pool).await?;
-        if let Some(user) = existing_email {
-             sqlx::query_as::<_, UserRow>(r#"UPDATE users SET oauth_provider=$2, oauth_id=$3, is_email_verified=TRUE, profile_picture_url=COALESCE($4, profile_picture_url), username=COALESCE(username, $5) WHERE id=$1 RETURNING *"#)
-             .bind(user.id).bind(provider).bind(&oauth_id).bind(&body.picture_url).bind(&body.username).fetch_one(&db.pool).await?
-        } else {
-             let username = body.username.clone().unwrap_or_else(|| email.split('@').next().unwrap_or("user").to_string());
-             sqlx::query_as::<_, UserRow>(r#"INSERT INTO users (username, email, role, is_email_verified, oauth_provider, oauth_id, profile_picture_url) VALUES ($1,$2,'user',TRUE,$3,$4,$5) RETURNING *"#)
-             .bind(username).bind(&email).bind(provider).bind(&oauth_id).bind(&body.picture_url).fetch_one(&db.pool).await?
+    let mut tx = db.pool.begin().await?;
+    let existing_email = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email=$1").bind(&identity.email).fetch_optional(&mut *tx).await?;
+    let user_id = if let Some(user) = existing_email {
+        // ผูกอัตโนมัติด้วยอีเมลเฉพาะตอน provider ยืนยันอีเมลให้จริง ไม่งั้นใครก็สมัคร provider ด้วยอีเมลคนอื่นมายึดบัญชีได้
+        // -> ต้อง login บัญชีเดิมแล้วผูกเองที่ /api/users/me/identities/link
+        if !identity.email_verified {
+            return Err(AppError::conflict("OAUTH_LINK_REQUIRED", "An account with this email already exists, sign in and link this provider from your account"));
         }
+        // oauth_provider/oauth_id เดิมเก็บ provider แรกไว้ (ไม่ทับ)
+        sqlx::query(r#"UPDATE users SET oauth_provider = CASE WHEN oauth_id IS NULL THEN $2 ELSE oauth_provider END, oauth_id = COALESCE(oauth_id, $3), is_email_verified=TRUE, profile_picture_url=COALESCE($4, profile_picture_url) WHERE id=$1"#)
+        .bind(user.id).bind(provider).bind(&identity.sub).bind(&identity.picture).execute(&mut *tx).await?;
+        user.id
+    } else {
+        let username = identity.email.split('@').next().unwrap_or("user").to_string();
+        let row = sqlx::query(r#"INSERT INTO users (username, email, role, is_email_verified, oauth_provider, oauth_id, profile_picture_url) VALUES ($1,$2,'user',TRUE,$3,$4,$5) RETURNING id"#)
+        .bind(username).bind(&identity.email).bind(provider).bind(&identity.sub).bind(&identity.picture).fetch_one(&mut *tx).await?;
+        row.get("id")
     };
+    identities_service::link(&mut *tx, user_id, provider, identity).await?;
+    tx.commit().await?;
 
-    // ✅ แก้ไข: ลบ name ออกจาก sign
-    let token = jwt::sign(u.id, u.email.clone(), u.role.clone(), env).map_err(|_| AppError::internal("Token sign error"))?;
-
-    Ok(AuthResponse {
-        token,
-        user: UserResponse { id: u.id, email: u.email, username: u.username, role: u.role, profile_picture_url: u.profile_picture_url, is_email_verified: u.is_email_verified },
-    })
+    Ok(user_id)
 }
 
-pub async fn forgot_password(db: &DB, body: ForgotPasswordBody) -> Result<(), AppError> {
+pub async fn forgot_password(db: &DB, env: &Env, accept_language: Option<&str>, body: ForgotPasswordBody) -> Result<(), AppError> {
     let email = body.email.trim().to_lowercase();
     let user = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1").bind(&email).fetch_optional(&db.pool).await?;
     if let Some(u) = user {
         let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
-        sqlx::query("INSERT INTO password_reset_tokens (user_id, token, expires_at) VALUES ($1, $2, NOW() + INTERVAL '30 minutes')").bind(u.id).bind(&token).execute(&db.pool).await?;
-        println!(">>> [MOCK RESET LINK] http://localhost:PORT/reset.html?token={} <<<", token);
+        sqlx::query("INSERT INTO password_reset_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, NOW() + INTERVAL '30 minutes')").bind(u.id).bind(token_hash::hash_token(&token)).execute(&db.pool).await?;
+        let link = format!("{}?token={}", env.reset_url_base, token);
+        let locale = Locale::resolve(u.locale.as_deref(), accept_language);
+        mail::queue(Template::PasswordReset { link: &link, minutes: 30 }.render(&u.email, locale, &env.app_name));
     }
     Ok(())
 }
 
-pub async fn reset_password(db: &DB, body: ResetPasswordBody) -> Result<(), AppError> {
-    if body.new_password.len() < 6 { return Err(AppError::bad_request("Password too short")); }
-    let row = sqlx::query("SELECT user_id FROM password_reset_tokens WHERE token = $1 AND is_used = FALSE AND expires_at > NOW()").bind(&body.token).fetch_optional(&db.pool).await?;
-    let user_id: i32 = match row { Some(r) => sqlx::Row::get(&r, "user_id"), None => return Err(AppError::bad_request("Invalid or expired token")) };
-    let pw_hash = hash(body.new_password, DEFAULT_COST).map_err(|_| AppError::internal("Hash error"))?;
-    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2").bind(pw_hash).bind(user_id).execute(&db.pool).await?;
-    sqlx::query("UPDATE password_reset_tokens SET is_used = TRUE WHERE token = $1").bind(&body.token).execute(&db.pool).await?;
+pub async fn reset_password(db: &DB, env: &Env, accept_language: Option<&str>, body: ResetPasswordBody) -> Result<(), AppError> {
+    // mark ว่าใช้แล้วใน UPDATE เดียว กันสอง request ใช้ token เดียวกัน (รหัสผ่านไม่ผ่าน policy = rollback ใช้ token ต่อได้)
+    let mut tx = db.pool.begin().await?;
+    let row = sqlx::query("UPDATE password_reset_tokens SET is_used = TRUE WHERE token_hash = $1 AND is_used = FALSE AND expires_at > NOW() RETURNING user_id").bind(token_hash::hash_token(body.token.trim())).fetch_optional(&mut *tx).await?;
+    let user_id: i32 = match row { Some(r) => r.get("user_id"), None => return Err(AppError::bad_request("Invalid or expired token")) };
+    let current = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE id = $1").bind(user_id).fetch_one(&mut *tx).await?;
+    password_policy::check(&body.new_password, Some(&current.email), current.username.as_deref())?;
+    let pw_hash = password::hash_password(&body.new_password).await?;
+    let u = sqlx::query_as::<_, UserRow>("UPDATE users SET password_hash = $1 WHERE id = $2 RETURNING *").bind(pw_hash).bind(user_id).fetch_one(&mut *tx).await?;
+    tx.commit().await?;
+    revocation::revoke_user(db, user_id, "password_reset").await?;
+    let locale = Locale::resolve(u.locale.as_deref(), accept_language);
+    mail::queue(Template::PasswordChanged.render(&u.email, locale, &env.app_name));
     Ok(())
-}
\ No newline at end of file
+}
+
+// --- Magic Link ---
+
+/// POST /api/auth/magic-link : ส่งลิงก์ login ไปที่อีเมล (ยังไม่มี user ก็สร้างให้เหมือน register)
+/// ตอบ ok เสมอ ไม่บอกว่าอีเมลนี้มีในระบบหรือไม่
+pub async fn send_magic_link(db: &DB, env: &Env, client: &ApiClient, accept_language: Option<&str>, body: MagicLinkBody) -> Result<(), AppError> {
+    let email = body.email.trim().to_lowercase();
+    if email.is_empty() { return Err(AppError::bad_request("Email is required")); }
+
+    let user = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1").bind(&email).fetch_optional(&db.pool).await?;
+    let (user_id, user_locale) = match user {
+        Some(u) => (u.id, u.locale),
+        None => {
+            let locale = accept_language.and_then(Locale::from_accept_language).map(|l| l.as_str());
+            let row: (i32,) = sqlx::query_as("INSERT INTO users (email, role, is_email_verified, locale) VALUES ($1, 'user', FALSE, $2) RETURNING id").bind(&email).bind(locale).fetch_one(&db.pool).await?;
+            (row.0, None)
+        }
+    };
+
+    // เก็บแค่ hash + ผูกกับ client ที่ขอ (เอาลิงก์ไปใช้กับแอปอื่นไม่ได้)
+    let token = token_hash::create_random_token();
+    sqlx::query(
+        r#"
+        INSERT INTO magic_link_tokens (token_hash, user_id, api_client_id, expires_at)
+        VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))
+        "#,
+    )
+    .bind(token_hash::hash_token(&token))
+    .bind(user_id)
+    .bind(client.id)
+    .bind(env.magic_link_ttl_secs as f64)
+    .execute(&db.pool)
+    .await?;
+
+    let link = format!("{}?token={}", env.magic_link_url_base, token);
+    let locale = Locale::resolve(user_locale.as_deref(), accept_language);
+    let minutes = (env.magic_link_ttl_secs + 59) / 60;
+    mail::queue(Template::MagicLink { link: &link, minutes }.render(&email, locale, &env.app_name));
+    Ok(())
+}
+
+/// POST /api/auth/magic-link/consume : แลก token (ครั้งเดียว) เป็น token จริง
+/// เปิดลิงก์ได้ = เป็นเจ้าของอีเมล -> ถือว่ายืนยันอีเมลแล้ว, เปิด 2FA ไว้ยังต้องผ่าน /2fa/verify
+pub async fn consume_magic_link(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: ConsumeMagicLinkBody) -> Result<LoginResponse, AppError> {
+    let row = sqlx::query(
+        "DELETE FROM magic_link_tokens WHERE token_hash = $1 AND api_client_id = $2 AND expires_at > NOW() RETURNING user_id",
+    )
+    .bind(token_hash::hash_token(body.token.trim()))
+    .bind(client.id)
+    .fetch_optional(&db.pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Err(AppError::unauthorized("MAGIC_LINK_INVALID", "Invalid or expired magic link"));
+    };
+    let user_id: i32 = row.get("user_id");
+
+    let u = sqlx::query_as::<_, UserRow>("UPDATE users SET is_email_verified = TRUE WHERE id = $1 RETURNING *")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("MAGIC_LINK_INVALID", "Invalid or expired magic link"))?;
+
+    issue_or_challenge(db, env, client, device, u).await
+}
@@ -0,0 +1,187 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use sqlx::Row;
+
+use crate::config::db::DB;
+use crate::config::env::ENV;
+use crate::core::errors::AppError;
+use crate::core::utils::token_hash;
+
+/// ผลการตรวจรหัสยืนยันอีเมล
+pub enum CodeCheck {
+    Verified,
+    // ผิด แต่ยังลองได้อีก
+    Invalid { attempts_left: i32 },
+    // ผิดครบ VERIFY_CODE_MAX_ATTEMPTS แล้ว รหัสถูกยกเลิก ต้องขอใหม่
+    Exhausted,
+    // ไม่มีรหัส หรือหมดอายุแล้ว
+    Expired,
+}
+
+impl CodeCheck {
+    /// แปลงเป็น error ของ /api/auth (Verified = Ok)
+    pub fn into_result(self) -> Result<(), AppError> {
+        match self {
+            Self::Verified => Ok(()),
+            Self::Invalid { attempts_left } => Err(AppError::bad_request("Invalid or expired code")
+                .with_details(json!({ "attempts_left": attempts_left }))),
+            Self::Exhausted => Err(AppError::too_many_requests(
+                "CODE_ATTEMPTS_EXCEEDED",
+                "Too many wrong codes, please request a new code",
+                None,
+            )),
+            Self::Expired => Err(AppError::bad_request("Invalid or expired code")),
+        }
+    }
+}
+
+/// วินาทีที่ต้องรอก่อนส่งรหัสให้อีเมลนี้ได้อีก (None = ส่งได้เลย)
+/// เช็คทั้ง cooldown ระหว่างรอบ และจำนวนครั้งต่อ 24 ชม.
+async fn send_wait<'e, E>(exec: E, email: &str) -> Result<Option<(&'static str, i64)>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let env = ENV.get().expect("ENV not initialized");
+    let r = sqlx::query(
+        r#"
+        SELECT
+          COUNT(*) AS sent,
+          CEIL(EXTRACT(EPOCH FROM (MAX(sent_at) + make_interval(secs => $2) - NOW())))::BIGINT AS cooldown_left,
+          CEIL(EXTRACT(EPOCH FROM (MIN(sent_at) + INTERVAL '1 day' - NOW())))::BIGINT AS day_left
+        FROM verification_code_sends
+        WHERE email = $1 AND sent_at > NOW() - INTERVAL '1 day'
+        "#,
+    )
+    .bind(email)
+    .bind(env.verify_code_resend_cooldown_secs as f64)
+    .fetch_one(exec)
+    .await?;
+
+    let sent: i64 = r.get("sent");
+    let cooldown_left: Option<i64> = r.get("cooldown_left");
+    let day_left: Option<i64> = r.get("day_left");
+
+    if sent >= env.verify_code_daily_cap {
+        return Ok(Some(("CODE_DAILY_LIMIT", day_left.unwrap_or(1).max(1))));
+    }
+    match cooldown_left {
+        Some(left) if left > 0 => Ok(Some(("CODE_RESEND_COOLDOWN", left))),
+        _ => Ok(None),
+    }
+}
+
+/// สุ่มรหัสใหม่อายุ 10 นาที แล้วออกผ่าน issue_code คืน (รหัส, วินาทีก่อนขอใหม่ได้)
+pub async fn issue(db: &DB, user_id: Option<i32>, email: &str) -> Result<(String, i64), AppError> {
+    let code: String = rand::Rng::gen_range(&mut rand::thread_rng(), 100000..999999).to_string();
+    let retry_after = issue_code(db, user_id, email, &code, Utc::now() + Duration::minutes(10)).await?;
+    Ok((code, retry_after))
+}
+
+/// นับการส่งรหัสให้อีเมลนี้ (ติด cooldown / เพดานรายวัน = 429) แล้วเก็บรหัสให้ user (ลบรหัสเก่าทิ้ง)
+///
+/// user_id = None (ไม่มีบัญชี / ยืนยันแล้ว) ยังนับการส่งเหมือนกัน ไม่ให้ใช้ 429 เดาว่ามีบัญชีไหม
+pub async fn issue_code(
+    db: &DB,
+    user_id: Option<i32>,
+    email: &str,
+    code: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<i64, AppError> {
+    let env = ENV.get().expect("ENV not initialized");
+    let mut tx = db.pool.begin().await?;
+
+    // กันสอง request ขอรหัสให้อีเมลเดียวกันพร้อมกันแล้วหลุด cooldown
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(email)
+        .execute(&mut *tx)
+        .await?;
+
+    if let Some((code, retry_after)) = send_wait(&mut *tx, email).await? {
+        let message = if code == "CODE_DAILY_LIMIT" {
+            "Too many codes requested today, please try again later"
+        } else {
+            "Please wait before requesting another code"
+        };
+        return Err(AppError::too_many_requests(code, message, Some(retry_after)));
+    }
+
+    if let Some(user_id) = user_id {
+        sqlx::query("DELETE FROM verification_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO verification_codes (user_id, code_hash, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(token_hash::hash_token(code.trim()))
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("INSERT INTO verification_code_sends (email) VALUES ($1)")
+        .bind(email)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(env.verify_code_resend_cooldown_secs)
+}
+
+/// ตรวจรหัส: นับครั้งก่อนแล้วค่อยเทียบ (atomic ต่อแถว) ยิงพร้อมกันหลายอันก็เกิน N ครั้งไม่ได้
+/// ถูก = ลบรหัสทั้งหมดของ user, ผิดครบ N = ลบรหัสนั้นทิ้ง
+pub async fn check(db: &DB, user_id: i32, code: &str) -> Result<CodeCheck, AppError> {
+    let env = ENV.get().expect("ENV not initialized");
+    let hashed = token_hash::hash_token(code.trim());
+
+    let rows = sqlx::query(
+        r#"
+        UPDATE verification_codes
+        SET attempts = attempts + 1
+        WHERE user_id = $1 AND expires_at > NOW() AND attempts < $2
+        RETURNING id, code_hash, attempts
+        "#,
+    )
+    .bind(user_id)
+    .bind(env.verify_code_max_attempts)
+    .fetch_all(&db.pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Ok(CodeCheck::Expired);
+    }
+
+    let matched = rows
+        .iter()
+        .find(|r| token_hash::constant_time_eq(&r.get::<String, _>("code_hash"), &hashed))
+        .map(|r| r.get::<i32, _>("id"));
+    if let Some(id) = matched {
+        // ยิงรหัสถูกพร้อมกันสองอัน ได้ผ่านแค่อันที่ลบแถวได้
+        let consumed = sqlx::query("DELETE FROM verification_codes WHERE id = $1 RETURNING id")
+            .bind(id)
+            .fetch_optional(&db.pool)
+            .await?;
+        if consumed.is_none() {
+            return Ok(CodeCheck::Expired);
+        }
+        sqlx::query("DELETE FROM verification_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&db.pool)
+            .await?;
+        return Ok(CodeCheck::Verified);
+    }
+
+    let attempts_left = rows
+        .iter()
+        .map(|r| env.verify_code_max_attempts - r.get::<i32, _>("attempts"))
+        .max()
+        .unwrap_or(0);
+    if attempts_left > 0 {
+        return Ok(CodeCheck::Invalid { attempts_left });
+    }
+
+    sqlx::query("DELETE FROM verification_codes WHERE user_id = $1 AND attempts >= $2")
+        .bind(user_id)
+        .bind(env.verify_code_max_attempts)
+        .execute(&db.pool)
+        .await?;
+    Ok(CodeCheck::Exhausted)
+}
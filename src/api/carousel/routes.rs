@@ -13,7 +13,7 @@ pub fn routes(db: DB) -> Router {
     // Public: list carousel
     let public_routes = Router::new().route("/", get(controller::list)).with_state(db.clone());
 
-    // Protected: create/update/delete (admin only)
+    // Protected: create/update/delete (carousel:write)
     let protected_routes = Router::new()
         .route("/", post(controller::create))
         .route(
@@ -21,10 +21,10 @@ pub fn routes(db: DB) -> Router {
             // Node (pure-api1) uses PUT, but keep PATCH for backward-compat
             put(controller::update)
                 .patch(controller::update)
-                .delete(controller::delete)
-                .route_layer(middleware::from_fn(jwt_auth::mw_require_admin))
-                .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth)),
+                .delete(controller::delete),
         )
+        .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("carousel:write")))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
         .with_state(db);
 
     Router::new().merge(public_routes).merge(protected_routes)
@@ -0,0 +1,24 @@
+use axum::{extract::{Query, State}, Json};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::env::Env;
+use crate::core::errors::AppError;
+use crate::core::mail::outbox;
+
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    pub to: Option<String>,
+    pub limit: Option<usize>,
+}
+
+// GET /api/dev/outbox?to=someone@example.com&limit=20
+pub async fn list_outbox(
+    State(env): State<Env>,
+    Query(q): Query<OutboxQuery>,
+) -> Result<Json<Value>, AppError> {
+    let items = outbox::list(&env.mail_outbox_dir, q.to.as_deref(), q.limit.unwrap_or(50).min(200))
+        .await
+        .map_err(|e| AppError::internal(format!("Unable to read outbox: {}", e)))?;
+    Ok(Json(json!({ "ok": true, "data": items })))
+}
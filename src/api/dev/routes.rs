@@ -0,0 +1,12 @@
+use axum::{routing::get, Router};
+
+use crate::config::env::Env;
+
+use super::controller;
+
+/// เครื่องมือสำหรับ dev/QA เท่านั้น (router จะ mount ให้เมื่อไม่ใช่ production)
+pub fn routes(env: Env) -> Router {
+    Router::new()
+        .route("/outbox", get(controller::list_outbox))
+        .with_state(env)
+}
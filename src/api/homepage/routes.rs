@@ -12,7 +12,7 @@ pub fn routes(db: DB) -> Router {
         .route(
             "/hero",
             put(controller::put_hero)
-                .route_layer(middleware::from_fn(jwt_auth::mw_require_admin))
+                .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("homepage:write")))
                 .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth)),
         )
         // pure-api1 compatibility
@@ -20,7 +20,7 @@ pub fn routes(db: DB) -> Router {
         .route(
             "/:section",
             put(controller::put_section)
-                .route_layer(middleware::from_fn(jwt_auth::mw_require_admin))
+                .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("homepage:write")))
                 .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth)),
         )
         .with_state(db)
@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::{LinkIdTokenBody, StartLinkBody};
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// GET /api/users/me/identities
+pub async fn list(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::list(&db, user.id).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/users/me/identities/link/:provider
+pub async fn link(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Path(provider): Path<String>,
+    Json(body): Json<LinkIdTokenBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::link_with_id_token(&db, &env, &user, &provider, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/users/me/identities/link/:provider/start
+pub async fn start_link(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Path(provider): Path<String>,
+    body: Option<Json<StartLinkBody>>,
+) -> Result<Json<Value>, AppError> {
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let out = service::start_link(&db, &env, &user, &provider, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// DELETE /api/users/me/identities/:id
+pub async fn unlink(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::unlink(&db, user.id, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
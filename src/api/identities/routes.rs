@@ -0,0 +1,24 @@
+use axum::{middleware, routing::{delete, get, post}, Router};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+pub fn routes(db: DB, env: Env) -> Router {
+    let list_routes = Router::new()
+        .route("/", get(controller::list))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // ผูก/ถอดบัญชี = เปลี่ยนวิธี login (admin สวมรอย / PAT ทำไม่ได้)
+    let manage_routes = Router::new()
+        .route("/link/:provider", post(controller::link))
+        .route("/link/:provider/start", post(controller::start_link))
+        .route("/:id", delete(controller::unlink))
+        .route_layer(middleware::from_fn(jwt_auth::mw_deny_delegated))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db, env));
+
+    Router::new().merge(list_routes).merge(manage_routes)
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct IdentityRow {
+    pub id: i32,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+// POST /api/users/me/identities/link/:provider (ID token จาก Sign-In SDK)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkIdTokenBody {
+    #[serde(alias = "id_token", alias = "credential")]
+    pub id_token: String,
+    // ต้องใส่ถ้าบัญชีมีรหัสผ่าน (ดู api::auth::reauth)
+    pub password: Option<String>,
+}
+
+// POST /api/users/me/identities/link/:provider/start (code flow ผ่าน browser)
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartLinkBody {
+    #[serde(alias = "redirect_uri")]
+    pub redirect_uri: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkStart {
+    // เปิด URL นี้ใน browser แล้ว callback จะ redirect กลับมาพร้อม ?linked=<provider>
+    pub url: String,
+}
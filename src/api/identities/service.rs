@@ -0,0 +1,167 @@
+use sqlx::Row;
+
+use crate::api::auth::reauth;
+use crate::api::oauth::{schema::OAuthIdentity, service as oauth_service};
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::{IdentityRow, LinkIdTokenBody, LinkStart, StartLinkBody};
+
+pub async fn list(db: &DB, user_id: i32) -> Result<Vec<IdentityRow>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, provider, email, created_at, last_login_at
+        FROM user_identities
+        WHERE user_id = $1
+        ORDER BY id ASC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| IdentityRow {
+            id: r.get("id"),
+            provider: r.get("provider"),
+            email: r.get("email"),
+            created_at: r.get("created_at"),
+            last_login_at: r.get("last_login_at"),
+        })
+        .collect())
+}
+
+/// login ด้วย identity ที่ผูกไว้แล้ว -> คืน user_id (และอัปเดต last_login_at)
+pub async fn find_user(db: &DB, provider: &str, identity: &OAuthIdentity) -> Result<Option<i32>, AppError> {
+    let row = sqlx::query(
+        r#"
+        UPDATE user_identities
+        SET last_login_at = NOW(), email = $3
+        WHERE provider = $1 AND subject = $2
+        RETURNING user_id
+        "#,
+    )
+    .bind(provider)
+    .bind(&identity.sub)
+    .bind(&identity.email)
+    .fetch_optional(&db.pool)
+    .await?;
+
+    Ok(row.map(|r| r.get("user_id")))
+}
+
+/// ผูก identity กับ user (ผูกซ้ำกับคนเดิมได้, ถ้าเป็นของคนอื่นอยู่แล้ว = conflict)
+pub async fn link<'e, E>(exec: E, user_id: i32, provider: &str, identity: &OAuthIdentity) -> Result<(), AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let row = sqlx::query(
+        r#"
+        INSERT INTO user_identities (user_id, provider, subject, email, last_login_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (provider, subject) DO UPDATE SET email = EXCLUDED.email
+        RETURNING user_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(provider)
+    .bind(&identity.sub)
+    .bind(&identity.email)
+    .fetch_one(exec)
+    .await?;
+
+    let owner: i32 = row.get("user_id");
+    if owner != user_id {
+        return Err(AppError::conflict("IDENTITY_IN_USE", "This account is already linked to another user"));
+    }
+    Ok(())
+}
+
+/// POST /api/users/me/identities/link/:provider
+pub async fn link_with_id_token(
+    db: &DB,
+    env: &Env,
+    user: &AuthUser,
+    provider: &str,
+    body: LinkIdTokenBody,
+) -> Result<Vec<IdentityRow>, AppError> {
+    reauth::require(db, env, user, body.password.as_deref()).await?;
+    let (provider, identity) = oauth_service::id_token_identity(env, provider, &body.id_token).await?;
+    link(&db.pool, user.id, &provider, &identity).await?;
+    list(db, user.id).await
+}
+
+/// POST /api/users/me/identities/link/:provider/start -> URL ของ provider (state ผูกกับ user นี้)
+pub async fn start_link(
+    db: &DB,
+    env: &Env,
+    user: &AuthUser,
+    provider: &str,
+    body: StartLinkBody,
+) -> Result<LinkStart, AppError> {
+    reauth::require(db, env, user, body.password.as_deref()).await?;
+    let url = oauth_service::authorize_url(db, env, provider, body.redirect_uri, Some(user.id)).await?;
+    Ok(LinkStart { url })
+}
+
+/// DELETE /api/users/me/identities/:id
+///
+/// ห้ามลบวิธี login สุดท้าย (ไม่มีรหัสผ่าน + ไม่เหลือ identity/passkey อื่น)
+pub async fn unlink(db: &DB, user_id: i32, id: i32) -> Result<(), AppError> {
+    let mut tx = db.pool.begin().await?;
+
+    // lock แถว user กันลบสอง identity พร้อมกันจนไม่เหลือทางเข้า
+    let user = sqlx::query("SELECT password_hash FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    let password_hash: Option<String> = user.get("password_hash");
+
+    let target = sqlx::query("SELECT provider, subject FROM user_identities WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("IDENTITY_NOT_FOUND", "Identity not found"))?;
+    let provider: String = target.get("provider");
+    let subject: String = target.get("subject");
+
+    if password_hash.is_none() {
+        let others: i64 = sqlx::query(
+            r#"
+            SELECT (SELECT COUNT(*) FROM user_identities WHERE user_id = $1 AND id <> $2)
+                 + (SELECT COUNT(*) FROM user_credentials WHERE user_id = $1) AS n
+            "#,
+        )
+        .bind(user_id)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get("n");
+
+        if others == 0 {
+            return Err(AppError::conflict(
+                "LAST_LOGIN_METHOD",
+                "Set a password before unlinking your last sign-in method",
+            ));
+        }
+    }
+
+    sqlx::query("DELETE FROM user_identities WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    // ล้างคอลัมน์เดิมใน users ถ้าชี้มาที่ identity นี้
+    sqlx::query("UPDATE users SET oauth_provider = NULL, oauth_id = NULL WHERE id = $1 AND oauth_provider = $2 AND oauth_id = $3")
+        .bind(user_id)
+        .bind(&provider)
+        .bind(&subject)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::ImpersonateBody;
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// Admin: POST /api/users/:id/impersonate
+pub async fn start(
+    State((db, env)): AppState,
+    Extension(admin): Extension<AuthUser>,
+    Extension(client): Extension<ApiClient>,
+    device: Device,
+    Path(user_id): Path<i32>,
+    Json(body): Json<ImpersonateBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::start(&db, &env, &admin, &client, &device, user_id, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// Admin: GET /api/users/:id/impersonations
+pub async fn list(
+    State((db, _)): AppState,
+    Path(user_id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::list(&db, user_id).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/users/me/impersonation/stop
+pub async fn stop(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    service::stop(&db, &user).await?;
+    Ok(Json(json!({ "ok": true })))
+}
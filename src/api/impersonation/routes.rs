@@ -0,0 +1,24 @@
+use axum::{middleware, routing::{get, post}, Router};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+/// /api/users/me/impersonation (ใช้ token สวมรอย)
+pub fn routes(db: DB, env: Env) -> Router {
+    Router::new()
+        .route("/stop", post(controller::stop))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db, env))
+}
+
+/// /api/users/:id/impersonate (users:impersonate)
+pub fn admin_routes(db: DB, env: Env) -> Router {
+    Router::new()
+        .route("/:id/impersonate", post(controller::start))
+        .route("/:id/impersonations", get(controller::list))
+        .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("users:impersonate")))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db, env))
+}
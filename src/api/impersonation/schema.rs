@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// POST /api/users/:id/impersonate
+#[derive(Debug, Deserialize)]
+pub struct ImpersonateBody {
+    // เหตุผล/เลข ticket (บังคับ เก็บลง audit)
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationToken {
+    pub impersonation_id: i32,
+    // access token ของ user เป้าหมาย (มี act = admin) ไม่มี refresh token
+    pub token: String,
+    pub expires_in: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationRow {
+    pub id: i32,
+    pub admin_id: i32,
+    pub user_id: i32,
+    pub reason: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    // active | stopped | expired
+    pub status: String,
+}
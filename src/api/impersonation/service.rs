@@ -0,0 +1,154 @@
+use sqlx::Row;
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::middleware::jwt_auth::AuthUser;
+use crate::core::utils::jwt::{self, Actor, Claims};
+use crate::core::utils::{rbac, revocation, token_hash};
+
+use super::schema::{ImpersonateBody, ImpersonationRow, ImpersonationToken};
+
+/// Admin: POST /api/users/:id/impersonate
+/// ออก token อายุสั้นของ user เป้าหมาย (act = admin) เปิด session แยก และบันทึก audit
+pub async fn start(
+    db: &DB,
+    env: &Env,
+    admin: &AuthUser,
+    client: &ApiClient,
+    device: &Device,
+    user_id: i32,
+    body: ImpersonateBody,
+) -> Result<ImpersonationToken, AppError> {
+    let reason = body.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::bad_request("Reason is required"));
+    }
+    if user_id == admin.id {
+        return Err(AppError::bad_request("Cannot impersonate yourself"));
+    }
+
+    let target = sqlx::query("SELECT email, role FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    let email: String = target.get("email");
+    let role: String = target.get("role");
+    // สวมรอยเป็น admin = ได้สิทธิ์ admin ของคนอื่น
+    if role == "admin" {
+        return Err(AppError::forbidden("IMPERSONATION_FORBIDDEN", "Cannot impersonate an admin"));
+    }
+    // role อื่นก็เหมือนกัน: เป้าหมายมี permission ที่ตัวเองไม่มี = ได้สิทธิ์เพิ่มผ่านการสวมรอย
+    let missing = rbac::missing_from(db, &admin.role, &role).await?;
+    if !missing.is_empty() {
+        return Err(AppError::forbidden("IMPERSONATION_FORBIDDEN", "Cannot impersonate a user with permissions you do not have")
+            .with_details(serde_json::json!({ "missing": missing })));
+    }
+
+    let ttl = env.impersonation_ttl_secs;
+    let mut tx = db.pool.begin().await?;
+    let sid: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO sessions (user_id, family_id, api_client_id, client_name, user_agent, ip, impersonator_id, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(secs => $8))
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(token_hash::create_random_token())
+    .bind(client.id)
+    .bind(&client.name)
+    .bind(&device.user_agent)
+    .bind(&device.ip)
+    .bind(admin.id)
+    .bind(ttl as f64)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let impersonation_id: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO impersonations (session_id, admin_id, user_id, reason, ip, user_agent, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW() + make_interval(secs => $7))
+        RETURNING id
+        "#,
+    )
+    .bind(sid)
+    .bind(admin.id)
+    .bind(user_id)
+    .bind(reason)
+    .bind(&device.ip)
+    .bind(&device.user_agent)
+    .bind(ttl as f64)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let iat = jwt::now_ts();
+    let claims = Claims {
+        sub: user_id,
+        email,
+        role,
+        exp: iat + ttl,
+        iat,
+        iss: env.jwt_issuer.clone(),
+        aud: client.name.clone(),
+        jti: token_hash::create_random_token(),
+        auth_time: iat,
+        sid,
+        act: Some(Actor { sub: admin.id }),
+    };
+    let token = jwt::encode_access(&claims, env).map_err(|_| AppError::internal("Token sign error"))?;
+
+    tracing::warn!("🎭 admin {} started impersonating user {} (#{}): {}", admin.id, user_id, impersonation_id, reason);
+    Ok(ImpersonationToken { impersonation_id, token, expires_in: ttl })
+}
+
+/// POST /api/users/me/impersonation/stop (ใช้ token สวมรอย) ปิด session แล้วบันทึกเวลาหยุด
+pub async fn stop(db: &DB, user: &AuthUser) -> Result<(), AppError> {
+    let (Some(admin_id), Some(sid)) = (user.act, user.sid) else {
+        return Err(AppError::bad_request("Not impersonating"));
+    };
+
+    // ปิด session แล้ว revocation ตั้ง impersonations.ended_at ให้เอง
+    revocation::revoke_session(db, user.id, sid, "impersonation").await?;
+
+    tracing::warn!("🎭 admin {} stopped impersonating user {}", admin_id, user.id);
+    Ok(())
+}
+
+/// Admin: GET /api/users/:id/impersonations (ประวัติการสวมรอยเป็น user นี้ ล่าสุดก่อน)
+pub async fn list(db: &DB, user_id: i32) -> Result<Vec<ImpersonationRow>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, admin_id, user_id, reason, ip, user_agent, started_at, expires_at, ended_at,
+               CASE WHEN ended_at IS NOT NULL THEN 'stopped'
+                    WHEN expires_at <= NOW() THEN 'expired'
+                    ELSE 'active' END AS status
+        FROM impersonations
+        WHERE user_id = $1
+        ORDER BY started_at DESC
+        LIMIT 200
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ImpersonationRow {
+            id: r.get("id"),
+            admin_id: r.get("admin_id"),
+            user_id: r.get("user_id"),
+            reason: r.get("reason"),
+            ip: r.get("ip"),
+            user_agent: r.get("user_agent"),
+            started_at: r.get("started_at"),
+            expires_at: r.get("expires_at"),
+            ended_at: r.get("ended_at"),
+            status: r.get("status"),
+        })
+        .collect())
+}
@@ -1,8 +1,11 @@
 use sqlx::Row;
 use crate::config::db::DB;
 use crate::core::errors::AppError;
+use crate::api::auth::verification::{self, CodeCheck};
+use crate::api::identities::service as identities_service;
+use crate::api::oauth::schema::OAuthIdentity;
+use crate::core::utils::{password, password_policy, revocation, token_hash};
 use super::schema::*;
-use bcrypt::{hash, DEFAULT_COST};
 use chrono::{DateTime, Utc};
 
 // --- User Management ---
@@ -19,7 +22,7 @@ pub async fn find_user(db: &DB, body: FindUserBody) -> Result<UserLite, AppError
             .fetch_optional(&db.pool)
             .await?
     } else if let (Some(p), Some(oid)) = (&body.provider, &body.oauth_id) {
-        sqlx::query("SELECT id, email, username, role, password_hash, oauth_provider, is_email_verified, profile_picture_url FROM users WHERE oauth_provider = $1 AND oauth_id = $2")
+        sqlx::query("SELECT u.id, u.email, u.username, u.role, u.password_hash, u.oauth_provider, u.is_email_verified, u.profile_picture_url FROM users u JOIN user_identities i ON i.user_id = u.id WHERE i.provider = $1 AND i.subject = $2")
             .bind(p)
             .bind(oid)
             .fetch_optional(&db.pool)
@@ -69,22 +72,37 @@ pub async fn create_user_email(db: &DB, body: CreateUserEmailBody) -> Result<Use
 
 pub async fn set_oauth_user(db: &DB, body: SetOAuthUserBody) -> Result<UserLite, AppError> {
     let email = body.email.trim().to_lowercase();
-    let existing = sqlx::query("SELECT id FROM users WHERE LOWER(email) = $1")
-        .bind(&email)
-        .fetch_optional(&db.pool)
+    // backend ที่เรียก internal เป็นคนยืนยันอีเมลกับ provider มาแล้ว
+    let identity = OAuthIdentity { sub: body.oauth_id.clone(), email: email.clone(), picture: body.picture_url.clone(), email_verified: true };
+
+    let mut tx = db.pool.begin().await?;
+
+    // ผูกไว้แล้วกับ user ไหนก็ใช้ user นั้น ไม่งั้นค่อยหาจากอีเมล
+    let linked = sqlx::query("SELECT user_id FROM user_identities WHERE provider = $1 AND subject = $2")
+        .bind(&body.provider)
+        .bind(&body.oauth_id)
+        .fetch_optional(&mut *tx)
         .await?;
+    let existing = match linked {
+        Some(r) => Some(r.get::<i32, _>("user_id")),
+        None => sqlx::query("SELECT id FROM users WHERE LOWER(email) = $1")
+            .bind(&email)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|r| r.get::<i32, _>("id")),
+    };
 
-    let r = if let Some(u) = existing {
-        let user_id: i32 = u.get("id");
+    let r = if let Some(user_id) = existing {
+        // ไม่ทับ provider เดิม (identity อื่นยังอยู่ใน user_identities)
         sqlx::query(
-            "UPDATE users SET oauth_provider = $2, oauth_id = $3, is_email_verified = TRUE, profile_picture_url = COALESCE($4, profile_picture_url), username = COALESCE(username, $5) WHERE id = $1 RETURNING id, email, username, role, password_hash, oauth_provider, is_email_verified, profile_picture_url"
+            "UPDATE users SET oauth_provider = CASE WHEN oauth_id IS NULL THEN $2 ELSE oauth_provider END, oauth_id = COALESCE(oauth_id, $3), is_email_verified = TRUE, profile_picture_url = COALESCE($4, profile_picture_url), username = COALESCE(username, $5) WHERE id = $1 RETURNING id, email, username, role, password_hash, oauth_provider, is_email_verified, profile_picture_url"
         )
         .bind(user_id)
         .bind(&body.provider)
         .bind(&body.oauth_id)
         .bind(&body.picture_url)
         .bind(&body.name)
-        .fetch_one(&db.pool)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         let username = body.name.clone().unwrap_or_else(|| email.split('@').next().unwrap_or("user").to_string());
@@ -96,10 +114,13 @@ pub async fn set_oauth_user(db: &DB, body: SetOAuthUserBody) -> Result<UserLite,
         .bind(&body.provider)
         .bind(&body.oauth_id)
         .bind(&body.picture_url)
-        .fetch_one(&db.pool)
+        .fetch_one(&mut *tx)
         .await?
     };
 
+    identities_service::link(&mut *tx, r.get("id"), &body.provider, &identity).await?;
+    tx.commit().await?;
+
     Ok(UserLite {
         id: r.get("id"),
         email: r.get("email"),
@@ -114,7 +135,8 @@ pub async fn set_oauth_user(db: &DB, body: SetOAuthUserBody) -> Result<UserLite,
 
 pub async fn set_username_password(db: &DB, body: SetUsernamePasswordBody) -> Result<UserLite, AppError> {
     let email = body.email.trim().to_lowercase();
-    let hash = hash(body.password, DEFAULT_COST).map_err(|_| AppError::internal("Hash error"))?;
+    password_policy::check(&body.password, Some(&email), Some(&body.username))?;
+    let hash = password::hash_password(&body.password).await?;
 
     let r = sqlx::query(
         "UPDATE users SET username = $2, password_hash = $3 WHERE LOWER(email) = $1 RETURNING id, email, username, role, password_hash, oauth_provider, is_email_verified, profile_picture_url"
@@ -192,18 +214,14 @@ pub async fn store_verification_code(db: &DB, body: StoreVerificationCodeBody) -
         .map_err(|_| AppError::bad_request("Invalid date format"))?
         .with_timezone(&Utc);
 
-    sqlx::query("DELETE FROM verification_codes WHERE user_id = $1")
+    let email: String = sqlx::query_scalar("SELECT LOWER(email) FROM users WHERE id = $1")
         .bind(body.user_id)
-        .execute(&db.pool)
-        .await?;
-
-    sqlx::query("INSERT INTO verification_codes (user_id, code, expires_at) VALUES ($1, $2, $3)")
-        .bind(body.user_id)
-        .bind(&body.code)
-        .bind(expires_at)
-        .execute(&db.pool)
-        .await?;
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
 
+    // ติด cooldown / เพดานรายวันเหมือนรหัสที่ /api/auth ออกเอง
+    verification::issue_code(db, Some(body.user_id), &email, &body.code, expires_at).await?;
     Ok(())
 }
 
@@ -220,33 +238,22 @@ pub async fn verify_code(db: &DB, body: VerifyCodeBody) -> Result<VerifyCodeResp
 
     let user_id: i32 = user.get("id");
 
-    let code_row = sqlx::query(
-        "SELECT id FROM verification_codes WHERE user_id = $1 AND code = $2 AND expires_at > NOW() LIMIT 1"
-    )
-    .bind(user_id)
-    .bind(&body.code)
-    .fetch_optional(&db.pool)
-    .await?;
-
-    if let Some(c) = code_row {
-        let code_id: i32 = c.get("id");
-        let mut tx = db.pool.begin().await?;
-
-        sqlx::query("DELETE FROM verification_codes WHERE id = $1")
-            .bind(code_id)
-            .execute(&mut *tx)
-            .await?;
-
-        sqlx::query("UPDATE users SET is_email_verified = TRUE WHERE id = $1")
-            .bind(user_id)
-            .execute(&mut *tx)
-            .await?;
-
-        tx.commit().await?;
-
-        Ok(VerifyCodeResponse { ok: true, user_id, reason: None })
-    } else {
-        Ok(VerifyCodeResponse { ok: false, user_id: 0, reason: Some("Invalid or expired code".into()) })
+    match verification::check(db, user_id, &body.code).await? {
+        CodeCheck::Verified => {
+            sqlx::query("UPDATE users SET is_email_verified = TRUE WHERE id = $1")
+                .bind(user_id)
+                .execute(&db.pool)
+                .await?;
+            Ok(VerifyCodeResponse { ok: true, user_id, reason: None })
+        }
+        CodeCheck::Exhausted => Ok(VerifyCodeResponse {
+            ok: false,
+            user_id: 0,
+            reason: Some("Too many wrong codes, please request a new code".into()),
+        }),
+        CodeCheck::Invalid { .. } | CodeCheck::Expired => {
+            Ok(VerifyCodeResponse { ok: false, user_id: 0, reason: Some("Invalid or expired code".into()) })
+        }
     }
 }
 
@@ -267,9 +274,9 @@ pub async fn create_reset_token(db: &DB, body: CreateResetTokenBody) -> Result<(
             .execute(&db.pool)
             .await?;
 
-        sqlx::query("INSERT INTO password_reset_tokens (user_id, token, expires_at) VALUES ($1, $2, $3)")
+        sqlx::query("INSERT INTO password_reset_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
             .bind(user_id)
-            .bind(&body.token)
+            .bind(token_hash::hash_token(body.token.trim()))
             .bind(expires_at)
             .execute(&db.pool)
             .await?;
@@ -279,21 +286,17 @@ pub async fn create_reset_token(db: &DB, body: CreateResetTokenBody) -> Result<(
 }
 
 pub async fn consume_reset_token(db: &DB, body: ConsumeResetTokenBody) -> Result<UserLite, AppError> {
+    // ใช้ได้ครั้งเดียว: mark + อ่าน user_id ใน UPDATE เดียว (ยิงพร้อมกันจะได้แค่ request เดียว)
     let row = sqlx::query(
-        "SELECT user_id FROM password_reset_tokens WHERE token = $1 AND expires_at > NOW() AND is_used = FALSE"
+        "UPDATE password_reset_tokens SET is_used = TRUE WHERE token_hash = $1 AND expires_at > NOW() AND is_used = FALSE RETURNING user_id"
     )
-    .bind(&body.token)
+    .bind(token_hash::hash_token(body.token.trim()))
     .fetch_optional(&db.pool)
     .await?;
 
     if let Some(r) = row {
         let user_id: i32 = r.get("user_id");
 
-        sqlx::query("UPDATE password_reset_tokens SET is_used = TRUE WHERE token = $1")
-            .bind(&body.token)
-            .execute(&db.pool)
-            .await?;
-
         let r = sqlx::query(
             "SELECT id, email, username, role, password_hash, oauth_provider, is_email_verified, profile_picture_url FROM users WHERE id = $1"
         )
@@ -317,12 +320,20 @@ pub async fn consume_reset_token(db: &DB, body: ConsumeResetTokenBody) -> Result
 }
 
 pub async fn set_password(db: &DB, body: SetPasswordBody) -> Result<(), AppError> {
-    let hash = hash(body.new_password, DEFAULT_COST).map_err(|_| AppError::internal("Hash error"))?;
+    let user = sqlx::query("SELECT email, username FROM users WHERE id = $1")
+        .bind(body.user_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    password_policy::check(&body.new_password, Some(user.get("email")), user.get("username"))?;
+    let hash = password::hash_password(&body.new_password).await?;
     sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
         .bind(body.user_id)
         .bind(hash)
         .execute(&db.pool)
         .await?;
+    // Node เรียกหลัง consume-reset-token -> token เดิมทั้งหมดต้องใช้ไม่ได้
+    revocation::revoke_user(db, body.user_id, "password_reset").await?;
     Ok(())
 }
 
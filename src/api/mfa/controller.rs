@@ -0,0 +1,72 @@
+use axum::{extract::State, Extension, Json};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::{MfaCodeBody, MfaVerifyBody};
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// GET /api/auth/2fa
+pub async fn status(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::status(&db, user.id).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/2fa/setup
+pub async fn setup(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::setup(&db, &env, &user).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/2fa/confirm
+pub async fn confirm(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<MfaCodeBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::confirm(&db, user.id, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/2fa/disable
+pub async fn disable(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<MfaCodeBody>,
+) -> Result<Json<Value>, AppError> {
+    service::disable(&db, &env, user.id, body).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+// POST /api/auth/2fa/recovery-codes
+pub async fn regenerate_recovery_codes(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<MfaCodeBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::regenerate_recovery_codes(&db, &env, user.id, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/2fa/verify
+pub async fn verify(
+    State((db, env)): AppState,
+    Extension(client): Extension<ApiClient>,
+    device: Device,
+    Json(body): Json<MfaVerifyBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::verify(&db, &env, &client, &device, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
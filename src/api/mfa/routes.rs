@@ -0,0 +1,30 @@
+use axum::{middleware, routing::{get, post}, Router};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+pub fn routes(db: DB, env: Env) -> Router {
+    let status_routes = Router::new()
+        .route("/", get(controller::status))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // ตั้งค่า 2FA (ต้อง login เอง ไม่ใช่ admin สวมรอย / PAT)
+    let manage_routes = Router::new()
+        .route("/setup", post(controller::setup))
+        .route("/confirm", post(controller::confirm))
+        .route("/disable", post(controller::disable))
+        .route("/recovery-codes", post(controller::regenerate_recovery_codes))
+        .route_layer(middleware::from_fn(jwt_auth::mw_deny_delegated))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // ขั้นที่สองของ login (ใช้ mfa_token จาก /login แทน JWT)
+    let verify_routes = Router::new()
+        .route("/verify", post(controller::verify))
+        .with_state((db, env));
+
+    Router::new().merge(status_routes).merge(manage_routes).merge(verify_routes)
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+// ยืนยัน/ปิด 2FA ด้วยรหัส 6 หลักจาก authenticator (หรือ recovery code)
+#[derive(Debug, Deserialize)]
+pub struct MfaCodeBody {
+    pub code: String,
+}
+
+// แลก challenge token จาก /login + รหัส -> token จริง
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaVerifyBody {
+    #[serde(alias = "mfa_token")]
+    pub mfa_token: String,
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaStatus {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaSetupResponse {
+    pub secret: String,
+    pub otpauth_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+// ตอบกลับจาก /login แทน AuthResponse เมื่อ user เปิด 2FA ไว้
+#[derive(Debug, Serialize)]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: usize,
+}
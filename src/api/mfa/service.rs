@@ -0,0 +1,272 @@
+use rand::{distributions::Uniform, Rng};
+use sqlx::Row;
+use std::time::{SystemTime, UNIX_EPOCH};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::api::auth::{schema::AuthResponse, service as auth_service};
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::middleware::jwt_auth::AuthUser;
+use crate::core::utils::{jwt, token_hash};
+
+use super::schema::*;
+
+const STEP_SECS: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+const MFA_PENDING: &str = "mfa_pending";
+const MFA_PENDING_TTL: usize = 5 * 60;
+
+fn build_totp(secret_b32: &str, issuer: &str, account: &str) -> Result<TOTP, AppError> {
+    let secret = Secret::Encoded(secret_b32.to_string())
+        .to_bytes()
+        .map_err(|_| AppError::internal("Invalid TOTP secret"))?;
+    TOTP::new(Algorithm::SHA1, 6, 1, STEP_SECS, secret, Some(issuer.to_string()), account.to_string())
+        .map_err(|_| AppError::internal("TOTP init error"))
+}
+
+/// หา time-step ที่รหัสตรง (ยอม clock skew ±1 step) คืน None ถ้าไม่ตรง
+fn matching_step(secret_b32: &str, code: &str) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let totp = build_totp(secret_b32, "", "").ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let current = now / STEP_SECS;
+
+    [current.saturating_sub(1), current, current + 1]
+        .into_iter()
+        .find(|step| totp.generate(step * STEP_SECS) == code)
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace('-', "")
+}
+
+/// recovery code รูปแบบ xxxx-xxxx (ตัดตัวที่สับสนง่ายอย่าง 0/o, 1/l ออก)
+fn generate_recovery_codes() -> Vec<String> {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    let dist = Uniform::from(0..ALPHABET.len());
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let raw: String = (0..8).map(|_| ALPHABET[rng.sample(dist)] as char).collect();
+            format!("{}-{}", &raw[..4], &raw[4..])
+        })
+        .collect()
+}
+
+async fn replace_recovery_codes(db: &DB, user_id: i32) -> Result<Vec<String>, AppError> {
+    let codes = generate_recovery_codes();
+    let mut tx = db.pool.begin().await?;
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for code in &codes {
+        sqlx::query("INSERT INTO mfa_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(token_hash::hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(codes)
+}
+
+/// เช็ค TOTP ของ user ที่เปิด 2FA แล้ว รหัสเดิม (step เดิม) ใช้ซ้ำไม่ได้
+async fn consume_totp(db: &DB, user_id: i32, secret: &str, code: &str) -> Result<bool, AppError> {
+    let Some(step) = matching_step(secret, code) else { return Ok(false) };
+
+    let res = sqlx::query(
+        "UPDATE user_mfa SET last_used_step = $2 WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)",
+    )
+    .bind(user_id)
+    .bind(step as i64)
+    .execute(&db.pool)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+async fn consume_recovery_code(db: &DB, user_id: i32, code: &str) -> Result<bool, AppError> {
+    let res = sqlx::query(
+        "UPDATE mfa_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(token_hash::hash_token(&normalize_recovery_code(code)))
+    .execute(&db.pool)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+/// ตรวจรหัสขั้นที่สอง: TOTP ก่อน ถ้าไม่ผ่านลอง recovery code
+/// รหัสผิดนับรวมกับ login ผิด (LOGIN_MAX_ATTEMPTS) ไม่งั้นเดารหัส 6 หลักได้ไม่จำกัด
+async fn check_second_factor(db: &DB, env: &Env, user_id: i32, code: &str) -> Result<(), AppError> {
+    if let Some(retry_after) = auth_service::lockout_remaining(db, user_id).await? {
+        return Err(AppError::account_locked(retry_after));
+    }
+
+    let row = sqlx::query("SELECT totp_secret FROM user_mfa WHERE user_id = $1 AND is_enabled = TRUE")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?;
+
+    let Some(row) = row else {
+        return Err(AppError::bad_request("Two-factor authentication is not enabled"));
+    };
+    let secret: String = row.get("totp_secret");
+
+    if consume_totp(db, user_id, &secret, code).await? || consume_recovery_code(db, user_id, code).await? {
+        auth_service::clear_failed_logins(db, user_id).await?;
+        return Ok(());
+    }
+    if let Some(retry_after) = auth_service::record_failed_login(db, env, user_id).await? {
+        return Err(AppError::account_locked(retry_after));
+    }
+    Err(AppError::unauthorized("MFA_INVALID_CODE", "Invalid two-factor code"))
+}
+
+/// ใช้ใน auth::service::login ว่าต้องถาม 2FA ต่อไหม
+pub async fn is_enabled(db: &DB, user_id: i32) -> Result<bool, AppError> {
+    let enabled: Option<bool> = sqlx::query_scalar("SELECT is_enabled FROM user_mfa WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?;
+    Ok(enabled.unwrap_or(false))
+}
+
+/// ออก challenge token อายุสั้นแทน JWT จริง (ใช้ได้ครั้งเดียว กับ client ที่ขอเท่านั้น ดู verify)
+pub async fn challenge(db: &DB, env: &Env, client: &ApiClient, user_id: i32) -> Result<MfaChallenge, AppError> {
+    let (mfa_token, jti) = jwt::sign_purpose(user_id, MFA_PENDING, MFA_PENDING_TTL, env)
+        .map_err(|_| AppError::internal("Token sign error"))?;
+
+    sqlx::query(
+        "INSERT INTO mfa_challenges (jti, user_id, api_client_id, expires_at) VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))",
+    )
+    .bind(&jti)
+    .bind(user_id)
+    .bind(client.id)
+    .bind(MFA_PENDING_TTL as f64)
+    .execute(&db.pool)
+    .await?;
+
+    Ok(MfaChallenge { mfa_required: true, mfa_token, expires_in: MFA_PENDING_TTL })
+}
+
+pub async fn status(db: &DB, user_id: i32) -> Result<MfaStatus, AppError> {
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .fetch_one(&db.pool)
+        .await?;
+
+    Ok(MfaStatus { enabled: is_enabled(db, user_id).await?, recovery_codes_remaining: remaining })
+}
+
+/// POST /api/auth/2fa/setup : สร้าง secret ใหม่ (ยังไม่เปิดใช้จนกว่าจะ confirm)
+pub async fn setup(db: &DB, env: &Env, user: &AuthUser) -> Result<MfaSetupResponse, AppError> {
+    if is_enabled(db, user.id).await? {
+        return Err(AppError::conflict("MFA_ALREADY_ENABLED", "Two-factor authentication is already enabled"));
+    }
+
+    let Secret::Encoded(secret) = Secret::generate_secret().to_encoded() else {
+        return Err(AppError::internal("TOTP secret error"));
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_mfa (user_id, totp_secret, is_enabled)
+        VALUES ($1, $2, FALSE)
+        ON CONFLICT (user_id) DO UPDATE SET totp_secret = EXCLUDED.totp_secret, last_used_step = NULL
+        "#,
+    )
+    .bind(user.id)
+    .bind(&secret)
+    .execute(&db.pool)
+    .await?;
+
+    let otpauth_url = build_totp(&secret, &env.app_name, &user.email)?.get_url();
+    Ok(MfaSetupResponse { secret, otpauth_url })
+}
+
+/// POST /api/auth/2fa/confirm : ยืนยันรหัสแรก -> เปิด 2FA + ออก recovery codes (โชว์ครั้งเดียว)
+pub async fn confirm(db: &DB, user_id: i32, body: MfaCodeBody) -> Result<RecoveryCodesResponse, AppError> {
+    let row = sqlx::query("SELECT totp_secret, is_enabled FROM user_mfa WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?;
+
+    let Some(row) = row else {
+        return Err(AppError::bad_request("Call /2fa/setup first"));
+    };
+    if row.get::<bool, _>("is_enabled") {
+        return Err(AppError::conflict("MFA_ALREADY_ENABLED", "Two-factor authentication is already enabled"));
+    }
+
+    let secret: String = row.get("totp_secret");
+    if !consume_totp(db, user_id, &secret, &body.code).await? {
+        return Err(AppError::unauthorized("MFA_INVALID_CODE", "Invalid two-factor code"));
+    }
+
+    sqlx::query("UPDATE user_mfa SET is_enabled = TRUE, enabled_at = NOW() WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&db.pool)
+        .await?;
+
+    let recovery_codes = replace_recovery_codes(db, user_id).await?;
+    Ok(RecoveryCodesResponse { recovery_codes })
+}
+
+/// POST /api/auth/2fa/disable : ต้องยืนยันด้วยรหัส (TOTP หรือ recovery code)
+pub async fn disable(db: &DB, env: &Env, user_id: i32, body: MfaCodeBody) -> Result<(), AppError> {
+    check_second_factor(db, env, user_id, &body.code).await?;
+
+    let mut tx = db.pool.begin().await?;
+    sqlx::query("DELETE FROM user_mfa WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// POST /api/auth/2fa/recovery-codes : ออกชุดใหม่ (ชุดเก่าใช้ไม่ได้ทันที)
+pub async fn regenerate_recovery_codes(db: &DB, env: &Env, user_id: i32, body: MfaCodeBody) -> Result<RecoveryCodesResponse, AppError> {
+    check_second_factor(db, env, user_id, &body.code).await?;
+    let recovery_codes = replace_recovery_codes(db, user_id).await?;
+    Ok(RecoveryCodesResponse { recovery_codes })
+}
+
+/// POST /api/auth/2fa/verify : mfa_token + รหัส -> access/refresh token
+/// mfa_token ถูกใช้ไปทันทีแม้รหัสผิด (ต้อง login ใหม่เพื่อขอ challenge ใหม่)
+pub async fn verify(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: MfaVerifyBody) -> Result<AuthResponse, AppError> {
+    let invalid = || AppError::unauthorized("MFA_TOKEN_INVALID", "Invalid or expired MFA token");
+    let claims = jwt::verify_purpose(&body.mfa_token, MFA_PENDING).map_err(|_| invalid())?;
+
+    let consumed = sqlx::query(
+        "DELETE FROM mfa_challenges WHERE jti = $1 AND user_id = $2 AND api_client_id = $3 AND expires_at > NOW()",
+    )
+    .bind(&claims.jti)
+    .bind(claims.sub)
+    .bind(client.id)
+    .execute(&db.pool)
+    .await?
+    .rows_affected()
+        == 1;
+    if !consumed {
+        return Err(invalid());
+    }
+
+    check_second_factor(db, env, claims.sub, &body.code).await?;
+    auth_service::issue_for_user(db, env, client, device, claims.sub).await
+}
@@ -17,14 +17,23 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::config::{db::DB, env::Env};
-use crate::core::middleware::{api_key, jwt_auth};
+use crate::core::middleware::api_key;
 
 pub mod admin;
 pub mod auth;
 pub mod carousel;
+pub mod dev;
 pub mod homepage;
+pub mod identities;
+pub mod impersonation;
 pub mod internal;
+pub mod mfa;
+pub mod oauth;
+pub mod passkeys;
+pub mod roles;
 pub mod root;
+pub mod sessions;
+pub mod tokens;
 pub mod users;
 pub mod download;
 
@@ -82,13 +91,18 @@ pub fn router(db: DB, env: Env) -> Router {
             config: governor_conf.clone(),
         });
 
+    // OAuth redirect/callback เปิดจาก browser (ไม่มี x-api-key) จึงอยู่นอก api_routes
+    let oauth_browser_routes = oauth::routes::browser_routes(db.clone(), env.clone())
+        .layer(GovernorLayer {
+            config: governor_conf.clone(),
+        });
+
     // Protected User Routes (Admin)
     // ✅ แก้ไข: ส่ง env.clone() ไปด้วย และไม่ต้องใส่ .route_layer ซ้ำ เพราะใน users::routes ใส่ไว้แล้ว
     let users_routes = users::routes::routes(db.clone(), env.clone());
 
-    // Protected Admin Routes
-    let admin_routes = admin::routes::routes(db.clone())
-        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth));
+    // Protected Admin Routes (mw_jwt_auth ใส่ไว้ใน admin::routes แล้ว)
+    let admin_routes = admin::routes::routes(db.clone());
 
     // Data Routes
     let homepage_routes = homepage::routes::routes(db.clone());
@@ -99,15 +113,21 @@ pub fn router(db: DB, env: Env) -> Router {
     let internal_routes = internal::routes::routes(db.clone());
 
     // API Group
-    let api_routes = Router::new()
+    let mut api_routes = Router::new()
         .nest("/auth", auth_routes)
         .nest("/users", users_routes)
         .nest("/homepage", homepage_routes)
         .nest("/carousel", carousel_routes)
         .nest("/download", download_routes)
         .nest("/admin", admin_routes)
-        .nest("/internal", internal_routes)
-        .layer(middleware::from_fn(api_key::mw_api_key_auth));
+        .nest("/internal", internal_routes);
+
+    // Dev-only: ดูอีเมลที่ถูกเก็บใน outbox (ไม่เปิดบน production)
+    if env.node_env != "production" && env.mail_backend == "outbox" {
+        api_routes = api_routes.nest("/dev", dev::routes::routes(env.clone()));
+    }
+
+    let api_routes = api_routes.layer(middleware::from_fn(api_key::mw_api_key_auth));
 
     let root_routes = root::routes::routes();
 
@@ -129,6 +149,7 @@ pub fn router(db: DB, env: Env) -> Router {
     Router::new()
         .merge(root_routes)
         .nest("/api", api_routes)
+        .nest("/api/auth/oauth", oauth_browser_routes)
         .fallback(fallback_handler)
         // Global Layers
         .layer(DefaultBodyLimit::max(2 * 1024 * 1024))
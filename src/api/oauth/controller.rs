@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Redirect,
+    Extension, Json,
+};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+
+use super::schema::{AuthorizeQuery, CallbackQuery, ExchangeBody, IdTokenBody};
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// GET /api/auth/oauth/:provider (browser)
+pub async fn authorize(
+    State((db, env)): AppState,
+    Path(provider): Path<String>,
+    Query(q): Query<AuthorizeQuery>,
+) -> Result<Redirect, AppError> {
+    let url = service::authorize_url(&db, &env, &provider, q.redirect_uri, None).await?;
+    Ok(Redirect::to(&url))
+}
+
+// GET /api/auth/oauth/:provider/callback (browser)
+pub async fn callback(
+    State((db, env)): AppState,
+    Path(provider): Path<String>,
+    Query(q): Query<CallbackQuery>,
+) -> Result<Redirect, AppError> {
+    let url = service::callback(&db, &env, &provider, q).await?;
+    Ok(Redirect::to(&url))
+}
+
+// POST /api/auth/oauth/:provider
+pub async fn id_token_login(
+    State((db, env)): AppState,
+    Extension(client): Extension<ApiClient>,
+    device: Device,
+    Path(provider): Path<String>,
+    Json(body): Json<IdTokenBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::id_token_login(&db, &env, &client, &device, &provider, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/oauth/exchange
+pub async fn exchange(
+    State((db, env)): AppState,
+    Extension(client): Extension<ApiClient>,
+    device: Device,
+    Json(body): Json<ExchangeBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::exchange(&db, &env, &client, &device, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
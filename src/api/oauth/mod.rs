@@ -0,0 +1,5 @@
+pub mod controller;
+pub mod providers;
+pub mod routes;
+pub mod schema;
+pub mod service;
@@ -0,0 +1,70 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config::oauth::OAuthProvider;
+use crate::core::errors::AppError;
+
+const DISCOVERY_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// endpoint ที่ใช้จริงของ provider (ค่าจาก config ทับค่าจาก discovery)
+#[derive(Clone, Debug)]
+pub struct Endpoints {
+    pub auth_url: String,
+    pub token_url: String,
+    pub jwks_uri: Option<String>,
+    pub userinfo_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Discovery {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+    userinfo_endpoint: Option<String>,
+}
+
+static DISCOVERY_CACHE: OnceLock<Mutex<HashMap<String, (Instant, Discovery)>>> = OnceLock::new();
+
+async fn discover(issuer: &str) -> Result<Discovery, AppError> {
+    let cache = DISCOVERY_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((at, doc)) = cache.lock().unwrap().get(issuer)
+        && at.elapsed() < DISCOVERY_TTL
+    {
+        return Ok(doc.clone());
+    }
+
+    let url = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+    let doc: Discovery = reqwest::get(&url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::internal(format!("OIDC discovery failed: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| AppError::internal(format!("Invalid OIDC discovery document: {}", e)))?;
+
+    cache.lock().unwrap().insert(issuer.to_string(), (Instant::now(), doc.clone()));
+    Ok(doc)
+}
+
+pub async fn endpoints(p: &OAuthProvider) -> Result<Endpoints, AppError> {
+    let needs_discovery = p.auth_url.is_none() || p.token_url.is_none() || p.jwks_uri.is_none();
+    let doc = match p.issuers.first() {
+        Some(issuer) if needs_discovery => Some(discover(issuer).await?),
+        _ => None,
+    };
+
+    let auth_url = p.auth_url.clone().or_else(|| doc.as_ref().map(|d| d.authorization_endpoint.clone()));
+    let token_url = p.token_url.clone().or_else(|| doc.as_ref().map(|d| d.token_endpoint.clone()));
+    let (Some(auth_url), Some(token_url)) = (auth_url, token_url) else {
+        return Err(AppError::internal(format!("OAuth provider {} has no endpoints", p.name)));
+    };
+
+    Ok(Endpoints {
+        auth_url,
+        token_url,
+        jwks_uri: p.jwks_uri.clone().or_else(|| doc.as_ref().and_then(|d| d.jwks_uri.clone())),
+        userinfo_url: p.userinfo_url.clone().or_else(|| doc.as_ref().and_then(|d| d.userinfo_endpoint.clone())),
+    })
+}
@@ -0,0 +1,21 @@
+use axum::{routing::{get, post}, Router};
+
+use crate::config::{db::DB, env::Env};
+
+use super::controller;
+
+/// เปิดผ่าน browser (redirect ไป/กลับจาก provider) จึงไม่มี x-api-key
+pub fn browser_routes(db: DB, env: Env) -> Router {
+    Router::new()
+        .route("/:provider", get(controller::authorize))
+        .route("/:provider/callback", get(controller::callback))
+        .with_state((db, env))
+}
+
+/// login ด้วย ID token / แลก login code จาก redirect เป็น token (ผ่าน x-api-key ตามปกติ)
+pub fn routes(db: DB, env: Env) -> Router {
+    Router::new()
+        .route("/exchange", post(controller::exchange))
+        .route("/:provider", post(controller::id_token_login))
+        .with_state((db, env))
+}
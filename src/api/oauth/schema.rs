@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+// GET /api/auth/oauth/:provider?redirect_uri=... (ไม่ส่ง = ใช้ OAUTH_REDIRECT_URLS ตัวแรก)
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeQuery {
+    pub redirect_uri: Option<String>,
+}
+
+// provider redirect กลับมาพร้อม code+state หรือ error
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+// POST /api/auth/oauth/exchange
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeBody {
+    pub code: String,
+}
+
+// POST /api/auth/oauth/:provider (Sign-In SDK ฝั่ง client ส่ง ID token มาให้ server ตรวจเอง)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdTokenBody {
+    #[serde(alias = "id_token", alias = "credential")]
+    pub id_token: String,
+}
+
+// response จาก token endpoint ของ provider
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub id_token: Option<String>,
+    pub access_token: Option<String>,
+}
+
+/// ตัวตนจาก provider หลังตรวจแล้ว (ผ่าน claim mapping)
+#[derive(Debug, Clone)]
+pub struct OAuthIdentity {
+    pub sub: String,
+    pub email: String,
+    pub picture: Option<String>,
+    // provider ยืนยันอีเมลเองใน token (email_verified = true) ไม่ใช่แค่ตั้ง TRUST_EMAIL
+    // ใช้ตัดสินว่าผูกเข้ากับบัญชีเดิมที่มีอีเมลเดียวกันอัตโนมัติได้ไหม
+    pub email_verified: bool,
+}
@@ -0,0 +1,335 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use reqwest::{header, Url};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+
+use crate::api::auth::schema::LoginResponse;
+use crate::api::auth::service as auth_service;
+use crate::api::identities::service as identities_service;
+use crate::config::{db::DB, env::Env, oauth::OAuthProvider};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::utils::{id_token, token_hash};
+
+use super::providers::{self, Endpoints};
+use super::schema::{CallbackQuery, ExchangeBody, IdTokenBody, OAuthIdentity, TokenResponse};
+
+fn provider<'a>(env: &'a Env, name: &str) -> Result<&'a OAuthProvider, AppError> {
+    env.oauth_provider(name)
+        .ok_or_else(|| AppError::not_found("OAUTH_PROVIDER_NOT_FOUND", "Unknown OAuth provider"))
+}
+
+/// ปลายทางต้องตรงกับที่ตั้งไว้ใน OAUTH_REDIRECT_URLS เท่านั้น (กัน open redirect)
+fn pick_redirect(env: &Env, requested: Option<String>) -> Result<String, AppError> {
+    match requested {
+        None => env
+            .oauth_redirect_urls
+            .first()
+            .cloned()
+            .ok_or_else(|| AppError::internal("OAUTH_REDIRECT_URLS is not configured")),
+        Some(r) if env.oauth_redirect_urls.contains(&r) => Ok(r),
+        Some(_) => Err(AppError::bad_request("redirect_uri is not allowed")),
+    }
+}
+
+fn with_params(base: &str, params: &[(&str, &str)]) -> Result<String, AppError> {
+    Url::parse_with_params(base, params)
+        .map(String::from)
+        .map_err(|_| AppError::internal(format!("Invalid URL: {}", base)))
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+// บาง provider ส่ง id เป็นตัวเลข (GitHub) หรือ email_verified เป็น string
+fn claim_str(claims: &Value, key: &str) -> Option<String> {
+    match claims.get(key)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn claim_bool(claims: &Value, key: &str) -> bool {
+    match claims.get(key) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => s == "true",
+        _ => false,
+    }
+}
+
+/// claims ที่ตรวจแล้ว -> ตัวตนตาม claim mapping ของ provider
+fn identity(p: &OAuthProvider, claims: &Value) -> Result<OAuthIdentity, AppError> {
+    let sub = claim_str(claims, &p.claims.sub)
+        .ok_or_else(|| AppError::unauthorized("OAUTH_PROFILE_INVALID", "Provider did not return a user id"))?;
+    let email = claim_str(claims, &p.claims.email)
+        .ok_or_else(|| AppError::unauthorized("OAUTH_PROFILE_INVALID", "Provider did not return an email"))?;
+    let email_verified = claim_bool(claims, &p.claims.email_verified);
+    if !p.trust_email && !email_verified {
+        return Err(AppError::unauthorized("EMAIL_NOT_VERIFIED", "Provider email is not verified"));
+    }
+    Ok(OAuthIdentity {
+        sub,
+        email: email.to_lowercase(),
+        picture: claim_str(claims, &p.claims.picture),
+        email_verified,
+    })
+}
+
+async fn verify_id_token(p: &OAuthProvider, ep: &Endpoints, token: &str) -> Result<Value, AppError> {
+    id_token::verify(token, ep.jwks_uri.as_deref(), &p.client_secret, &p.issuers, &p.client_id).await
+}
+
+/// ตรวจ ID token ของ provider แล้วคืน (ชื่อ provider, ตัวตน)
+pub async fn id_token_identity(
+    env: &Env,
+    provider_name: &str,
+    token: &str,
+) -> Result<(String, OAuthIdentity), AppError> {
+    let p = provider(env, provider_name)?;
+    let ep = providers::endpoints(p).await?;
+    let claims = verify_id_token(p, &ep, token).await?;
+    Ok((p.name.clone(), identity(p, &claims)?))
+}
+
+/// POST /api/auth/oauth/:provider -> ตรวจ ID token แล้ว login
+pub async fn id_token_login(
+    db: &DB,
+    env: &Env,
+    client: &ApiClient,
+    device: &Device,
+    provider_name: &str,
+    body: IdTokenBody,
+) -> Result<LoginResponse, AppError> {
+    let (provider, identity) = id_token_identity(env, provider_name, &body.id_token).await?;
+    let user_id = auth_service::oauth_login_user(db, &provider, &identity).await?;
+    auth_service::issue_or_challenge_for_user(db, env, client, device, user_id).await
+}
+
+/// GET /api/auth/oauth/:provider -> URL ของหน้า consent ของ provider
+///
+/// link_user_id = ผูก identity เพิ่มให้ user นี้แทนการ login (ดู api::identities)
+pub async fn authorize_url(
+    db: &DB,
+    env: &Env,
+    provider_name: &str,
+    redirect_uri: Option<String>,
+    link_user_id: Option<i32>,
+) -> Result<String, AppError> {
+    let p = provider(env, provider_name)?;
+    let ep = providers::endpoints(p).await?;
+    let redirect = pick_redirect(env, redirect_uri)?;
+
+    let state = token_hash::create_random_token();
+    let verifier = token_hash::create_random_token();
+
+    sqlx::query(
+        r#"
+        INSERT INTO oauth_states (state_hash, provider, code_verifier, redirect_uri, link_user_id, expires_at)
+        VALUES ($1, $2, $3, $4, $5, NOW() + INTERVAL '10 minutes')
+        "#,
+    )
+    .bind(token_hash::hash_token(&state))
+    .bind(&p.name)
+    .bind(&verifier)
+    .bind(&redirect)
+    .bind(link_user_id)
+    .execute(&db.pool)
+    .await?;
+
+    with_params(
+        &ep.auth_url,
+        &[
+            ("client_id", p.client_id.as_str()),
+            ("redirect_uri", p.redirect_uri.as_str()),
+            ("response_type", "code"),
+            ("scope", p.scopes.as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", pkce_challenge(&verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+}
+
+/// แลก authorization code ที่ token endpoint (ส่ง PKCE verifier ไปด้วย)
+async fn exchange_code(
+    p: &OAuthProvider,
+    ep: &Endpoints,
+    code: &str,
+    verifier: &str,
+) -> Result<TokenResponse, AppError> {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("client_id", p.client_id.as_str()),
+        ("redirect_uri", p.redirect_uri.as_str()),
+        ("code_verifier", verifier),
+    ];
+    // public client (PKCE อย่างเดียว) ไม่มี secret
+    if !p.client_secret.is_empty() {
+        form.push(("client_secret", p.client_secret.as_str()));
+    }
+
+    let res = reqwest::Client::new()
+        .post(&ep.token_url)
+        // GitHub ตอบ form-encoded ถ้าไม่ขอ JSON
+        .header(header::ACCEPT, "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| AppError::internal(format!("Token request failed: {}", e)))?;
+
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        tracing::warn!("OAuth token exchange with {} failed ({}): {}", p.name, status, body);
+        return Err(AppError::unauthorized("OAUTH_EXCHANGE_FAILED", "Authorization code exchange failed"));
+    }
+
+    res.json()
+        .await
+        .map_err(|_| AppError::unauthorized("OAUTH_EXCHANGE_FAILED", "Invalid token response"))
+}
+
+/// provider ที่ไม่ใช่ OIDC (ไม่มี id_token) ใช้ access token ไปดึง profile แทน
+async fn fetch_userinfo(env: &Env, url: &str, access_token: &str) -> Result<Value, AppError> {
+    reqwest::Client::new()
+        .get(url)
+        .bearer_auth(access_token)
+        .header(header::ACCEPT, "application/json")
+        // GitHub บังคับต้องมี User-Agent
+        .header(header::USER_AGENT, env.app_name.as_str())
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::unauthorized("OAUTH_EXCHANGE_FAILED", format!("Userinfo request failed: {}", e)))?
+        .json()
+        .await
+        .map_err(|_| AppError::unauthorized("OAUTH_EXCHANGE_FAILED", "Invalid userinfo response"))
+}
+
+async fn identity_from_code(
+    env: &Env,
+    p: &OAuthProvider,
+    code: &str,
+    verifier: &str,
+) -> Result<OAuthIdentity, AppError> {
+    let ep = providers::endpoints(p).await?;
+    let token = exchange_code(p, &ep, code, verifier).await?;
+
+    let claims = match (&token.id_token, &token.access_token, &ep.userinfo_url) {
+        (Some(t), _, _) if !p.issuers.is_empty() => verify_id_token(p, &ep, t).await?,
+        (_, Some(access), Some(url)) => fetch_userinfo(env, url, access).await?,
+        _ => {
+            return Err(AppError::unauthorized(
+                "OAUTH_EXCHANGE_FAILED",
+                "Token response has no usable id_token or access_token",
+            ));
+        }
+    };
+
+    identity(p, &claims)
+}
+
+/// หลังได้ตัวตนจาก provider: ผูกเข้ากับ user ที่ขอ link หรือ login ตามปกติ (คืน query ที่จะต่อท้าย redirect)
+async fn complete_callback(
+    db: &DB,
+    env: &Env,
+    p: &OAuthProvider,
+    code: &str,
+    verifier: &str,
+    link_user_id: Option<i32>,
+) -> Result<(&'static str, String), AppError> {
+    let identity = identity_from_code(env, p, code, verifier).await?;
+
+    if let Some(user_id) = link_user_id {
+        identities_service::link(&db.pool, user_id, &p.name, &identity).await?;
+        return Ok(("linked", p.name.clone()));
+    }
+
+    let user_id = auth_service::oauth_login_user(db, &p.name, &identity).await?;
+
+    // ส่ง token ผ่าน URL ไม่ได้ -> ให้ code ใช้ครั้งเดียวอายุสั้นไปแลกที่ /exchange แทน
+    let login_code = token_hash::create_random_token();
+    sqlx::query(
+        r#"
+        INSERT INTO oauth_login_codes (code_hash, user_id, expires_at)
+        VALUES ($1, $2, NOW() + INTERVAL '1 minute')
+        "#,
+    )
+    .bind(token_hash::hash_token(&login_code))
+    .bind(user_id)
+    .execute(&db.pool)
+    .await?;
+
+    Ok(("code", login_code))
+}
+
+/// GET /api/auth/oauth/:provider/callback -> URL ของ frontend (?code=, ?linked= หรือ ?error=)
+///
+/// state ผิด/หมดอายุ = ไม่รู้ว่าจะพากลับไปไหน จึงตอบเป็น error ตรงๆ
+pub async fn callback(
+    db: &DB,
+    env: &Env,
+    provider_name: &str,
+    q: CallbackQuery,
+) -> Result<String, AppError> {
+    let p = provider(env, provider_name)?;
+
+    let state = q.state.unwrap_or_default();
+    let row = sqlx::query(
+        r#"
+        DELETE FROM oauth_states
+        WHERE state_hash = $1 AND provider = $2
+        RETURNING code_verifier, redirect_uri, link_user_id, expires_at > NOW() AS is_valid
+        "#,
+    )
+    .bind(token_hash::hash_token(&state))
+    .bind(&p.name)
+    .fetch_optional(&db.pool)
+    .await?;
+
+    let Some(row) = row.filter(|r| r.get::<bool, _>("is_valid")) else {
+        return Err(AppError::bad_request("Invalid or expired OAuth state"));
+    };
+    let verifier: String = row.get("code_verifier");
+    let redirect: String = row.get("redirect_uri");
+    let link_user_id: Option<i32> = row.get("link_user_id");
+
+    if let Some(err) = q.error {
+        return with_params(&redirect, &[("error", err.as_str())]);
+    }
+    let Some(code) = q.code else {
+        return with_params(&redirect, &[("error", "invalid_request")]);
+    };
+
+    match complete_callback(db, env, p, &code, &verifier, link_user_id).await {
+        Ok((key, value)) => with_params(&redirect, &[(key, value.as_str())]),
+        Err(e) => {
+            let reason = match &e {
+                AppError::Http { code, .. } => code.to_lowercase(),
+                _ => "server_error".to_string(),
+            };
+            tracing::warn!("OAuth callback for {} failed: {}", p.name, e);
+            with_params(&redirect, &[("error", reason.as_str())])
+        }
+    }
+}
+
+/// POST /api/auth/oauth/exchange: one-time login code -> access + refresh token (หรือ challenge ถ้าเปิด 2FA)
+pub async fn exchange(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: ExchangeBody) -> Result<LoginResponse, AppError> {
+    let row = sqlx::query(
+        "DELETE FROM oauth_login_codes WHERE code_hash = $1 AND expires_at > NOW() RETURNING user_id",
+    )
+    .bind(token_hash::hash_token(body.code.trim()))
+    .fetch_optional(&db.pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Err(AppError::unauthorized("OAUTH_CODE_INVALID", "Invalid or expired login code"));
+    };
+    auth_service::issue_or_challenge_for_user(db, env, client, device, row.get("user_id")).await
+}
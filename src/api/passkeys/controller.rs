@@ -0,0 +1,71 @@
+use axum::{extract::{Path, State}, Extension, Json};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::{FinishLoginBody, FinishRegistrationBody, StartLoginBody};
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// GET /api/auth/passkeys
+pub async fn list(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let items = service::list(&db, user.id).await?;
+    Ok(Json(json!({ "ok": true, "data": items })))
+}
+
+// DELETE /api/auth/passkeys/:id
+pub async fn remove(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::remove(&db, user.id, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+// POST /api/auth/passkeys/register/start
+pub async fn start_registration(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::start_registration(&db, &env, user.id).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/passkeys/register/finish
+pub async fn finish_registration(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<FinishRegistrationBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::finish_registration(&db, &env, user.id, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/passkeys/login/start
+pub async fn start_login(
+    State((db, env)): AppState,
+    Json(body): Json<StartLoginBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::start_login(&db, &env, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/auth/passkeys/login/finish
+pub async fn finish_login(
+    State((db, env)): AppState,
+    Extension(client): Extension<ApiClient>,
+    device: Device,
+    Json(body): Json<FinishLoginBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::finish_login(&db, &env, &client, &device, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
@@ -0,0 +1,30 @@
+use axum::{middleware, routing::{delete, get, post}, Router};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+pub fn routes(db: DB, env: Env) -> Router {
+    let list_routes = Router::new()
+        .route("/", get(controller::list))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // จัดการ passkey ของตัวเอง (ต้อง login เอง ไม่ใช่ admin สวมรอย / PAT)
+    let manage_routes = Router::new()
+        .route("/:id", delete(controller::remove))
+        .route("/register/start", post(controller::start_registration))
+        .route("/register/finish", post(controller::finish_registration))
+        .route_layer(middleware::from_fn(jwt_auth::mw_deny_delegated))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // login ด้วย passkey (public)
+    let login_routes = Router::new()
+        .route("/login/start", post(controller::start_login))
+        .route("/login/finish", post(controller::finish_login))
+        .with_state((db, env));
+
+    Router::new().merge(list_routes).merge(manage_routes).merge(login_routes)
+}
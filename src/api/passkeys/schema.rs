@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential,
+    RequestChallengeResponse,
+};
+
+// ผลจาก navigator.credentials.create() ส่งกลับมาตามที่ได้ (JSON ตามสเปค WebAuthn)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishRegistrationBody {
+    #[serde(alias = "challenge_id")]
+    pub challenge_id: String,
+    pub name: Option<String>,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartLoginBody {
+    pub email: String,
+}
+
+// ผลจาก navigator.credentials.get()
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishLoginBody {
+    #[serde(alias = "challenge_id")]
+    pub challenge_id: String,
+    pub credential: PublicKeyCredential,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegistrationChallenge {
+    pub challenge_id: String,
+    pub options: CreationChallengeResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginChallenge {
+    pub challenge_id: String,
+    pub options: RequestChallengeResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyRow {
+    pub id: i32,
+    pub name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
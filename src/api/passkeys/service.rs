@@ -0,0 +1,251 @@
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::{types::Json, Row};
+use webauthn_rs::prelude::{Passkey, PasskeyAuthentication, PasskeyRegistration, Url, Uuid, Webauthn, WebauthnBuilder};
+
+use crate::api::auth::{schema::AuthResponse, service as auth_service};
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::utils::token_hash;
+
+use super::schema::*;
+
+const CHALLENGE_TTL_SECS: f64 = 5.0 * 60.0;
+
+/// สร้าง Webauthn จาก config: origin = FRONTEND_URL ทุกตัว, rp_id = WEBAUTHN_RP_ID หรือ host ของ origin แรก
+fn webauthn(env: &Env) -> Result<Webauthn, AppError> {
+    let origins: Vec<Url> = env.frontend_urls.iter().filter_map(|u| Url::parse(u).ok()).collect();
+    let first = origins.first().ok_or_else(|| AppError::internal("FRONTEND_URL is not configured"))?;
+
+    let rp_id = if env.webauthn_rp_id.is_empty() {
+        first.host_str().unwrap_or("localhost").to_string()
+    } else {
+        env.webauthn_rp_id.clone()
+    };
+
+    let mut builder = WebauthnBuilder::new(&rp_id, first)
+        .map_err(|e| AppError::internal(format!("WebAuthn config error: {}", e)))?
+        .rp_name(&env.app_name);
+    for origin in origins.iter().skip(1) {
+        builder = builder.append_allowed_origin(origin);
+    }
+
+    builder.build().map_err(|e| AppError::internal(format!("WebAuthn config error: {}", e)))
+}
+
+/// เก็บ state ของ ceremony ไว้ฝั่ง server ระหว่าง start -> finish (ใช้ได้ครั้งเดียว)
+async fn save_challenge<T: Serialize + Sync>(db: &DB, user_id: i32, kind: &str, state: &T) -> Result<String, AppError> {
+    let id = token_hash::create_random_token();
+    sqlx::query(
+        r#"
+        INSERT INTO webauthn_challenges (id, user_id, kind, state, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+        "#,
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(kind)
+    .bind(Json(state))
+    .bind(CHALLENGE_TTL_SECS)
+    .execute(&db.pool)
+    .await?;
+    Ok(id)
+}
+
+async fn take_challenge<T: DeserializeOwned>(db: &DB, id: &str, kind: &str) -> Result<(i32, T), AppError> {
+    let row = sqlx::query(
+        "DELETE FROM webauthn_challenges WHERE id = $1 AND kind = $2 AND expires_at > NOW() RETURNING user_id, state",
+    )
+    .bind(id)
+    .bind(kind)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::bad_request("Invalid or expired passkey challenge"))?;
+
+    let Json(state): Json<T> = row.try_get("state").map_err(|_| AppError::internal("Invalid passkey challenge state"))?;
+    Ok((row.get("user_id"), state))
+}
+
+/// passkey ทั้งหมดของ user (id ในตาราง, Passkey)
+async fn load_passkeys(db: &DB, user_id: i32) -> Result<Vec<(i32, Passkey)>, AppError> {
+    let rows = sqlx::query("SELECT id, passkey FROM user_credentials WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(&db.pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for r in rows {
+        let Json(pk): Json<Passkey> = r.try_get("passkey").map_err(|_| AppError::internal("Invalid stored passkey"))?;
+        out.push((r.get("id"), pk));
+    }
+    Ok(out)
+}
+
+pub async fn list(db: &DB, user_id: i32) -> Result<Vec<PasskeyRow>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, name, created_at, last_used_at
+        FROM user_credentials
+        WHERE user_id = $1
+        ORDER BY id ASC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| PasskeyRow {
+            id: r.get("id"),
+            name: r.get("name"),
+            created_at: r.get("created_at"),
+            last_used_at: r.get("last_used_at"),
+        })
+        .collect())
+}
+
+pub async fn remove(db: &DB, user_id: i32, id: i32) -> Result<(), AppError> {
+    let res = sqlx::query("DELETE FROM user_credentials WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(AppError::not_found("PASSKEY_NOT_FOUND", "Passkey not found"));
+    }
+    Ok(())
+}
+
+/// POST /register/start : ออก options ให้ navigator.credentials.create()
+pub async fn start_registration(db: &DB, env: &Env, user_id: i32) -> Result<RegistrationChallenge, AppError> {
+    // user handle ของ WebAuthn ต้องคงที่ต่อ user และไม่ควรเป็น id ตรง ๆ -> สุ่มครั้งแรกแล้วเก็บไว้
+    let row = sqlx::query(
+        r#"
+        UPDATE users SET webauthn_user_id = COALESCE(webauthn_user_id, $2)
+        WHERE id = $1
+        RETURNING email, username, webauthn_user_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(Uuid::new_v4())
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+
+    let email: String = row.get("email");
+    let username: Option<String> = row.get("username");
+    let handle: Uuid = row.get("webauthn_user_id");
+
+    // กันลงทะเบียน authenticator ตัวเดิมซ้ำ
+    let exclude = load_passkeys(db, user_id)
+        .await?
+        .iter()
+        .map(|(_, pk)| pk.cred_id().clone())
+        .collect::<Vec<_>>();
+
+    let (options, state) = webauthn(env)?
+        .start_passkey_registration(handle, &email, username.as_deref().unwrap_or(&email), Some(exclude))
+        .map_err(|e| AppError::bad_request(format!("Passkey registration error: {}", e)))?;
+
+    let challenge_id = save_challenge(db, user_id, "register", &state).await?;
+    Ok(RegistrationChallenge { challenge_id, options })
+}
+
+/// POST /register/finish : ตรวจ attestation แล้วเก็บ public key
+pub async fn finish_registration(db: &DB, env: &Env, user_id: i32, body: FinishRegistrationBody) -> Result<PasskeyRow, AppError> {
+    let (owner_id, state): (i32, PasskeyRegistration) = take_challenge(db, &body.challenge_id, "register").await?;
+    if owner_id != user_id {
+        return Err(AppError::bad_request("Invalid or expired passkey challenge"));
+    }
+
+    let passkey = webauthn(env)?
+        .finish_passkey_registration(&body.credential, &state)
+        .map_err(|e| AppError::bad_request(format!("Passkey registration failed: {}", e)))?;
+
+    let name = body
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "Passkey".into());
+
+    let row = sqlx::query(
+        r#"
+        INSERT INTO user_credentials (user_id, credential_id, passkey, name)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (credential_id) DO NOTHING
+        RETURNING id, name, created_at, last_used_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(hex::encode(passkey.cred_id()))
+    .bind(Json(&passkey))
+    .bind(name)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::conflict("PASSKEY_EXISTS", "Passkey already registered"))?;
+
+    Ok(PasskeyRow {
+        id: row.get("id"),
+        name: row.get("name"),
+        created_at: row.get("created_at"),
+        last_used_at: row.get("last_used_at"),
+    })
+}
+
+/// POST /login/start : ออก options ให้ navigator.credentials.get() จาก passkey ของ email นี้
+pub async fn start_login(db: &DB, env: &Env, body: StartLoginBody) -> Result<LoginChallenge, AppError> {
+    let email = body.email.trim().to_lowercase();
+    let user_id: Option<i32> = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_optional(&db.pool)
+        .await?;
+
+    // ไม่บอกว่า email มีอยู่จริงไหม -> error เดียวกันทั้งสองกรณี
+    let not_found = || AppError::not_found("PASSKEY_NOT_FOUND", "No passkey registered for this account");
+    let user_id = user_id.ok_or_else(not_found)?;
+    let passkeys = load_passkeys(db, user_id).await?;
+    if passkeys.is_empty() {
+        return Err(not_found());
+    }
+
+    let creds: Vec<Passkey> = passkeys.into_iter().map(|(_, pk)| pk).collect();
+    let (options, state) = webauthn(env)?
+        .start_passkey_authentication(&creds)
+        .map_err(|e| AppError::bad_request(format!("Passkey login error: {}", e)))?;
+
+    let challenge_id = save_challenge(db, user_id, "login", &state).await?;
+    Ok(LoginChallenge { challenge_id, options })
+}
+
+/// POST /login/finish : ตรวจ assertion -> ออก token แบบเดียวกับ /login
+pub async fn finish_login(db: &DB, env: &Env, client: &ApiClient, device: &Device, body: FinishLoginBody) -> Result<AuthResponse, AppError> {
+    let (user_id, state): (i32, PasskeyAuthentication) = take_challenge(db, &body.challenge_id, "login").await?;
+
+    let result = webauthn(env)?
+        .finish_passkey_authentication(&body.credential, &state)
+        .map_err(|_| AppError::unauthorized("PASSKEY_INVALID", "Passkey verification failed"))?;
+
+    let (row_id, mut passkey) = load_passkeys(db, user_id)
+        .await?
+        .into_iter()
+        .find(|(_, pk)| pk.cred_id() == result.cred_id())
+        .ok_or_else(|| AppError::unauthorized("PASSKEY_INVALID", "Passkey verification failed"))?;
+
+    // counter / backup state เปลี่ยน -> บันทึกกลับ
+    if passkey.update_credential(&result) == Some(true) {
+        sqlx::query("UPDATE user_credentials SET passkey = $2 WHERE id = $1")
+            .bind(row_id)
+            .bind(Json(&passkey))
+            .execute(&db.pool)
+            .await?;
+    }
+    sqlx::query("UPDATE user_credentials SET last_used_at = NOW() WHERE id = $1")
+        .bind(row_id)
+        .execute(&db.pool)
+        .await?;
+
+    auth_service::issue_for_user(db, env, client, device, user_id).await
+}
@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde_json::json;
+
+use crate::config::db::DB;
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::{CreateRoleBody, UpdateRoleBody};
+use super::service;
+
+// GET /api/admin/permissions
+pub async fn list_permissions(State(db): State<DB>) -> Result<Json<serde_json::Value>, AppError> {
+    let items = service::list_permissions(&db).await?;
+    Ok(Json(json!({ "ok": true, "data": items })))
+}
+
+// GET /api/admin/roles
+pub async fn list_roles(State(db): State<DB>) -> Result<Json<serde_json::Value>, AppError> {
+    let items = service::list_roles(&db).await?;
+    Ok(Json(json!({ "ok": true, "data": items })))
+}
+
+// POST /api/admin/roles
+pub async fn create_role(
+    State(db): State<DB>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<CreateRoleBody>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let role = service::create_role(&db, &user, body).await?;
+    Ok(Json(json!({ "ok": true, "data": role })))
+}
+
+// PUT /api/admin/roles/:name
+pub async fn update_role(
+    State(db): State<DB>,
+    Extension(user): Extension<AuthUser>,
+    Path(name): Path<String>,
+    Json(body): Json<UpdateRoleBody>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let role = service::update_role(&db, &user, &name, body).await?;
+    Ok(Json(json!({ "ok": true, "data": role })))
+}
+
+// DELETE /api/admin/roles/:name
+pub async fn delete_role(
+    State(db): State<DB>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    service::delete_role(&db, &name).await?;
+    Ok(Json(json!({ "ok": true })))
+}
@@ -0,0 +1,17 @@
+use axum::{middleware, routing::{get, put}, Router};
+
+use crate::config::db::DB;
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+/// /api/admin/roles + /api/admin/permissions (roles:manage)
+pub fn routes(db: DB) -> Router {
+    Router::new()
+        .route("/roles", get(controller::list_roles).post(controller::create_role))
+        .route("/roles/:name", put(controller::update_role).delete(controller::delete_role))
+        .route("/permissions", get(controller::list_permissions))
+        .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("roles:manage")))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state(db)
+}
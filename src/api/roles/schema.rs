@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct PermissionRow {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoleRow {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_system: bool,
+    pub permissions: Vec<String>,
+    // จำนวน user ที่ใช้ role นี้อยู่
+    pub user_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// POST /api/admin/roles
+#[derive(Debug, Deserialize)]
+pub struct CreateRoleBody {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+// PUT /api/admin/roles/:name (permissions = แทนที่ชุดเดิมทั้งหมด, ไม่ส่ง = ไม่แก้)
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoleBody {
+    pub description: Option<String>,
+    pub permissions: Option<Vec<String>>,
+}
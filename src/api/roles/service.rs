@@ -0,0 +1,178 @@
+use sqlx::{Postgres, Row, Transaction};
+
+use crate::config::db::DB;
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+use crate::core::utils::rbac;
+
+use super::schema::{CreateRoleBody, PermissionRow, RoleRow, UpdateRoleBody};
+
+// admin ได้ทุก permission เสมอ (แก้ไม่ได้ กัน lock ตัวเองออกจาก /api/admin/roles)
+const LOCKED_ROLE: &str = "admin";
+
+/// ชื่อ role: a-z 0-9 _ - ขึ้นต้นด้วยตัวอักษร ยาว 2-32
+fn valid_name(name: &str) -> bool {
+    let len = name.len();
+    (2..=32).contains(&len)
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// GET /api/admin/permissions
+pub async fn list_permissions(db: &DB) -> Result<Vec<PermissionRow>, AppError> {
+    let rows = sqlx::query("SELECT name, description FROM permissions ORDER BY name")
+        .fetch_all(&db.pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| PermissionRow { name: r.get("name"), description: r.get("description") })
+        .collect())
+}
+
+const ROLE_SELECT: &str = r#"
+    SELECT r.name, r.description, r.is_system, r.created_at,
+           COALESCE(ARRAY(SELECT rp.permission FROM role_permissions rp WHERE rp.role_name = r.name ORDER BY rp.permission), '{}') AS permissions,
+           (SELECT COUNT(*) FROM users u WHERE u.role = r.name) AS user_count
+    FROM roles r
+"#;
+
+fn to_role(r: &sqlx::postgres::PgRow) -> RoleRow {
+    RoleRow {
+        name: r.get("name"),
+        description: r.get("description"),
+        is_system: r.get("is_system"),
+        permissions: r.get("permissions"),
+        user_count: r.get("user_count"),
+        created_at: r.get("created_at"),
+    }
+}
+
+/// GET /api/admin/roles
+pub async fn list_roles(db: &DB) -> Result<Vec<RoleRow>, AppError> {
+    let rows = sqlx::query(&format!("{} ORDER BY r.is_system DESC, r.name", ROLE_SELECT))
+        .fetch_all(&db.pool)
+        .await?;
+    Ok(rows.iter().map(to_role).collect())
+}
+
+async fn get_role(db: &DB, name: &str) -> Result<RoleRow, AppError> {
+    let row = sqlx::query(&format!("{} WHERE r.name = $1", ROLE_SELECT))
+        .bind(name)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("ROLE_NOT_FOUND", "Role not found"))?;
+    Ok(to_role(&row))
+}
+
+/// แทนที่ permission ทั้งหมดของ role (ชื่อที่ไม่รู้จัก = 400)
+async fn set_permissions(tx: &mut Transaction<'_, Postgres>, role: &str, permissions: &[String]) -> Result<(), AppError> {
+    let mut perms: Vec<String> = permissions.iter().map(|p| p.trim().to_string()).collect();
+    perms.sort();
+    perms.dedup();
+
+    let known: Vec<String> = sqlx::query_scalar("SELECT name FROM permissions WHERE name = ANY($1)")
+        .bind(&perms)
+        .fetch_all(&mut **tx)
+        .await?;
+    if let Some(bad) = perms.iter().find(|p| !known.contains(p)) {
+        return Err(AppError::bad_request(format!("Unknown permission: {}", bad)));
+    }
+
+    sqlx::query("DELETE FROM role_permissions WHERE role_name = $1")
+        .bind(role)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("INSERT INTO role_permissions (role_name, permission) SELECT $1, UNNEST($2::TEXT[])")
+        .bind(role)
+        .bind(&perms)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// ให้ได้เฉพาะ permission ที่ตัวเองมี (ไม่งั้นคนที่มี roles:manage ใส่ทุกอย่างให้ role ตัวเองได้)
+async fn ensure_grantable(db: &DB, caller: &AuthUser, permissions: &[String]) -> Result<(), AppError> {
+    let wanted: Vec<String> = permissions.iter().map(|p| p.trim().to_string()).collect();
+    let missing = rbac::missing_permissions(db, &caller.role, &wanted).await?;
+    if !missing.is_empty() {
+        return Err(rbac::escalation(missing));
+    }
+    Ok(())
+}
+
+/// POST /api/admin/roles
+pub async fn create_role(db: &DB, caller: &AuthUser, body: CreateRoleBody) -> Result<RoleRow, AppError> {
+    let name = body.name.trim().to_lowercase();
+    if !valid_name(&name) {
+        return Err(AppError::bad_request("name must be 2-32 characters of a-z, 0-9, _ or - and start with a letter"));
+    }
+    ensure_grantable(db, caller, &body.permissions).await?;
+
+    let mut tx = db.pool.begin().await?;
+    let inserted = sqlx::query("INSERT INTO roles (name, description) VALUES ($1, $2) ON CONFLICT (name) DO NOTHING")
+        .bind(&name)
+        .bind(body.description.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+        .execute(&mut *tx)
+        .await?;
+    if inserted.rows_affected() == 0 {
+        return Err(AppError::conflict("ROLE_EXISTS", "Role already exists"));
+    }
+    set_permissions(&mut tx, &name, &body.permissions).await?;
+    tx.commit().await?;
+
+    tracing::info!("🛡️ role created: {} {:?}", name, body.permissions);
+    get_role(db, &name).await
+}
+
+/// PUT /api/admin/roles/:name (แก้ได้เฉพาะ role ที่ permission ไม่เกินของตัวเอง)
+pub async fn update_role(db: &DB, caller: &AuthUser, name: &str, body: UpdateRoleBody) -> Result<RoleRow, AppError> {
+    if name == LOCKED_ROLE {
+        return Err(AppError::conflict("ROLE_PROTECTED", "The admin role cannot be changed"));
+    }
+    let missing = rbac::missing_from(db, &caller.role, name).await?;
+    if !missing.is_empty() {
+        return Err(rbac::escalation(missing));
+    }
+    if let Some(perms) = &body.permissions {
+        ensure_grantable(db, caller, perms).await?;
+    }
+
+    let mut tx = db.pool.begin().await?;
+    let found = sqlx::query("UPDATE roles SET description = COALESCE($2, description) WHERE name = $1")
+        .bind(name)
+        .bind(body.description.as_deref().map(str::trim))
+        .execute(&mut *tx)
+        .await?;
+    if found.rows_affected() == 0 {
+        return Err(AppError::not_found("ROLE_NOT_FOUND", "Role not found"));
+    }
+    if let Some(perms) = &body.permissions {
+        set_permissions(&mut tx, name, perms).await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!("🛡️ role updated: {} {:?}", name, body.permissions);
+    get_role(db, name).await
+}
+
+/// DELETE /api/admin/roles/:name (role ระบบ และ role ที่ยังมี user ใช้อยู่ ลบไม่ได้)
+pub async fn delete_role(db: &DB, name: &str) -> Result<(), AppError> {
+    let role = get_role(db, name).await?;
+    if role.is_system {
+        return Err(AppError::conflict("ROLE_PROTECTED", "System roles cannot be deleted"));
+    }
+    if role.user_count > 0 {
+        return Err(AppError::conflict("ROLE_IN_USE", "Role is still assigned to users")
+            .with_details(serde_json::json!({ "user_count": role.user_count })));
+    }
+
+    // ถ้ามีคนถูกตั้ง role นี้พอดีระหว่างนี้ FK จะกันไว้
+    sqlx::query("DELETE FROM roles WHERE name = $1 AND is_system = FALSE")
+        .bind(name)
+        .execute(&db.pool)
+        .await?;
+
+    tracing::info!("🛡️ role deleted: {}", name);
+    Ok(())
+}
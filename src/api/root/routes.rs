@@ -1,6 +1,8 @@
-use axum::{routing::get, Router, Json};
+use axum::{http::header, response::IntoResponse, routing::get, Router, Json};
 use serde_json::json;
 
+use crate::core::utils::jwt_keys;
+
 pub fn routes() -> Router {
     Router::new()
         .route("/", get(|| async {
@@ -14,4 +16,13 @@ pub fn routes() -> Router {
         .route("/health", get(|| async {
             Json(json!({ "ok": true }))
         }))
+        // public key สำหรับให้ service อื่นตรวจ access token เอง (ว่างถ้ายังใช้ HS256)
+        .route("/.well-known/jwks.json", get(jwks))
+}
+
+async fn jwks() -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(jwt_keys::keys().jwks.clone()),
+    )
 }
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// GET /api/users/me/sessions
+pub async fn list(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::list(&db, user.id, user.sid).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// DELETE /api/users/me/sessions/:id
+pub async fn revoke(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::revoke(&db, user.id, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+// Admin: GET /api/users/:id/sessions
+pub async fn admin_list(
+    State((db, _)): AppState,
+    Path(user_id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::ensure_user(&db, user_id).await?;
+    let out = service::list(&db, user_id, None).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// Admin: DELETE /api/users/:id/sessions/:sid
+pub async fn admin_revoke(
+    State((db, _)): AppState,
+    Path((user_id, id)): Path<(i32, i32)>,
+) -> Result<Json<Value>, AppError> {
+    service::revoke(&db, user_id, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
@@ -0,0 +1,33 @@
+use axum::{middleware, routing::{delete, get}, Router};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+/// /api/users/me/sessions
+pub fn routes(db: DB, env: Env) -> Router {
+    let list_routes = Router::new()
+        .route("/", get(controller::list))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // ปิด device ต้องเป็นเจ้าของบัญชีเอง (admin สวมรอยจะ logout user ออกจากทุกเครื่องไม่ได้)
+    let revoke_routes = Router::new()
+        .route("/:id", delete(controller::revoke))
+        .route_layer(middleware::from_fn(jwt_auth::mw_deny_delegated))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db, env));
+
+    Router::new().merge(list_routes).merge(revoke_routes)
+}
+
+/// /api/users/:id/sessions (sessions:manage)
+pub fn admin_routes(db: DB, env: Env) -> Router {
+    Router::new()
+        .route("/:id/sessions", get(controller::admin_list))
+        .route("/:id/sessions/:sid", delete(controller::admin_revoke))
+        .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("sessions:manage")))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db, env))
+}
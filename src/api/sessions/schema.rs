@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct SessionRow {
+    pub id: i32,
+    // name ของ api client ที่ใช้ login (react-web, android-app, ...)
+    pub client_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    // session ของ token ที่ใช้เรียกอยู่ตอนนี้
+    pub current: bool,
+}
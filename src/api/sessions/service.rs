@@ -0,0 +1,96 @@
+use sqlx::Row;
+
+use crate::config::db::DB;
+use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::utils::revocation;
+
+use super::schema::SessionRow;
+
+/// สร้าง session ของ refresh token family (มีอยู่แล้ว = อัปเดต last_seen/เครื่องล่าสุด) คืน sessions.id
+pub async fn open<'e, E>(
+    exec: E,
+    user_id: i32,
+    family_id: &str,
+    client: &ApiClient,
+    device: &Device,
+) -> Result<i32, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let id: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO sessions (user_id, family_id, api_client_id, client_name, user_agent, ip)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (family_id) DO UPDATE
+        SET last_seen_at = NOW(),
+            user_agent = COALESCE(EXCLUDED.user_agent, sessions.user_agent),
+            ip = COALESCE(EXCLUDED.ip, sessions.ip)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(family_id)
+    .bind(client.id)
+    .bind(&client.name)
+    .bind(&device.user_agent)
+    .bind(&device.ip)
+    .fetch_one(exec)
+    .await?;
+    Ok(id)
+}
+
+/// session ที่ยังใช้งานได้ (ยังไม่ถูกปิด และ refresh token ยังไม่หมดอายุ) ล่าสุดก่อน
+pub async fn list(db: &DB, user_id: i32, current_sid: Option<i32>) -> Result<Vec<SessionRow>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT s.id, s.client_name, s.user_agent, s.ip, s.created_at, s.last_seen_at
+        FROM sessions s
+        WHERE s.user_id = $1 AND s.revoked_at IS NULL
+          AND EXISTS (
+            SELECT 1 FROM refresh_tokens r
+            WHERE r.family_id = s.family_id AND r.revoked_at IS NULL AND r.expires_at > NOW()
+          )
+        ORDER BY s.last_seen_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let id: i32 = r.get("id");
+            SessionRow {
+                id,
+                client_name: r.get("client_name"),
+                user_agent: r.get("user_agent"),
+                ip: r.get("ip"),
+                created_at: r.get("created_at"),
+                last_seen_at: r.get("last_seen_at"),
+                current: current_sid == Some(id),
+            }
+        })
+        .collect())
+}
+
+/// ปิด session (logout เครื่องนั้น) ไม่เจอ = 404
+pub async fn revoke(db: &DB, user_id: i32, id: i32) -> Result<(), AppError> {
+    if !revocation::revoke_session(db, user_id, id, "revoked").await? {
+        return Err(AppError::not_found("SESSION_NOT_FOUND", "Session not found"));
+    }
+    Ok(())
+}
+
+/// Admin: เช็คว่ามี user ก่อน (list ว่าง กับ ไม่มี user ต่างกัน)
+pub async fn ensure_user(db: &DB, user_id: i32) -> Result<(), AppError> {
+    let exists: Option<i32> = sqlx::query_scalar("SELECT id FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?;
+    exists
+        .map(|_| ())
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))
+}
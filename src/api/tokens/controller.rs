@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde_json::{json, Value};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+
+use super::schema::CreateTokenBody;
+use super::service;
+
+type AppState = State<(DB, Env)>;
+
+// GET /api/users/me/tokens
+pub async fn list(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::list(&db, user.id).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/users/me/tokens
+pub async fn create(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<CreateTokenBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::create(&db, &user, body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// DELETE /api/users/me/tokens/:id
+pub async fn revoke(
+    State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::revoke(&db, user.id, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
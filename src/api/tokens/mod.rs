@@ -0,0 +1,4 @@
+pub mod controller;
+pub mod routes;
+pub mod schema;
+pub mod service;
@@ -0,0 +1,24 @@
+use axum::{middleware, routing::{delete, get, post}, Router};
+
+use crate::config::{db::DB, env::Env};
+use crate::core::middleware::jwt_auth;
+
+use super::controller;
+
+/// /api/users/me/tokens
+pub fn routes(db: DB, env: Env) -> Router {
+    let manage_routes = Router::new()
+        .route("/", get(controller::list))
+        .route("/:id", delete(controller::revoke))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // สร้าง token ใหม่ต้อง login เอง (ใช้ PAT สร้าง PAT ต่อไม่ได้)
+    let create_routes = Router::new()
+        .route("/", post(controller::create))
+        .route_layer(middleware::from_fn(jwt_auth::mw_deny_delegated))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db, env));
+
+    Router::new().merge(manage_routes).merge(create_routes)
+}
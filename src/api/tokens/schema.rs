@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// POST /api/users/me/tokens
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTokenBody {
+    pub name: String,
+    // read | write | admin (ว่าง = read)
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    // ไม่ส่ง = 30 วัน, สูงสุด 365 วัน
+    #[serde(alias = "expires_in_days")]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenRow {
+    pub id: i32,
+    pub name: String,
+    // pat_xxxxxxxx (ไม่ใช่ token เต็ม)
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedToken {
+    // โชว์ครั้งเดียว เก็บไว้เองให้ดี
+    pub token: String,
+    #[serde(flatten)]
+    pub info: TokenRow,
+}
@@ -0,0 +1,112 @@
+use sqlx::Row;
+
+use crate::config::db::DB;
+use crate::core::errors::AppError;
+use crate::core::middleware::jwt_auth::AuthUser;
+use crate::core::utils::{pat, token_hash};
+
+use super::schema::{CreateTokenBody, CreatedToken, TokenRow};
+
+const DEFAULT_TTL_DAYS: i64 = 30;
+const MAX_TTL_DAYS: i64 = 365;
+const MAX_TOKENS_PER_USER: i64 = 50;
+
+fn to_row(r: &sqlx::postgres::PgRow) -> TokenRow {
+    TokenRow {
+        id: r.get("id"),
+        name: r.get("name"),
+        token_prefix: r.get("token_prefix"),
+        scopes: r.get("scopes"),
+        expires_at: r.get("expires_at"),
+        last_used_at: r.get("last_used_at"),
+        created_at: r.get("created_at"),
+    }
+}
+
+/// POST /api/users/me/tokens : สร้าง token ใหม่ (คืน token ตัวจริงครั้งเดียว เก็บแค่ hash)
+pub async fn create(db: &DB, user: &AuthUser, body: CreateTokenBody) -> Result<CreatedToken, AppError> {
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(AppError::bad_request("Name is required (max 100 characters)"));
+    }
+
+    let mut scopes: Vec<String> = body.scopes.iter().map(|s| s.trim().to_lowercase()).collect();
+    if scopes.is_empty() {
+        scopes.push("read".to_string());
+    }
+    scopes.sort();
+    scopes.dedup();
+    if let Some(bad) = scopes.iter().find(|s| !pat::SCOPES.contains(&s.as_str())) {
+        return Err(AppError::bad_request(format!("Unknown scope: {}", bad)));
+    }
+    if scopes.iter().any(|s| s == "admin") && user.role == "user" {
+        return Err(AppError::forbidden("FORBIDDEN", "Your role has no permissions for the admin scope"));
+    }
+
+    let days = body.expires_in_days.unwrap_or(DEFAULT_TTL_DAYS);
+    if !(1..=MAX_TTL_DAYS).contains(&days) {
+        return Err(AppError::bad_request(format!("expiresInDays must be between 1 and {}", MAX_TTL_DAYS)));
+    }
+
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM personal_access_tokens WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()",
+    )
+    .bind(user.id)
+    .fetch_one(&db.pool)
+    .await?;
+    if active >= MAX_TOKENS_PER_USER {
+        return Err(AppError::conflict("PAT_LIMIT", "Too many access tokens, revoke some first"));
+    }
+
+    let (token, prefix) = pat::generate();
+    let r = sqlx::query(
+        r#"
+        INSERT INTO personal_access_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(days => $6))
+        RETURNING id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+        "#,
+    )
+    .bind(user.id)
+    .bind(name)
+    .bind(token_hash::hash_token(&token))
+    .bind(&prefix)
+    .bind(&scopes)
+    .bind(days as i32)
+    .fetch_one(&db.pool)
+    .await?;
+
+    Ok(CreatedToken { token, info: to_row(&r) })
+}
+
+/// GET /api/users/me/tokens (เฉพาะที่ยังใช้ได้ ไม่มี token ตัวจริง)
+pub async fn list(db: &DB, user_id: i32) -> Result<Vec<TokenRow>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+        FROM personal_access_tokens
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows.iter().map(to_row).collect())
+}
+
+/// DELETE /api/users/me/tokens/:id
+pub async fn revoke(db: &DB, user_id: i32, id: i32) -> Result<(), AppError> {
+    let res = sqlx::query(
+        "UPDATE personal_access_tokens SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&db.pool)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(AppError::not_found("PAT_NOT_FOUND", "Access token not found"));
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use axum::{
     extract::{Path, State},
+    http::{header, HeaderMap},
     Extension,
     Json,
 };
@@ -7,9 +8,11 @@ use serde_json::{json, Value};
 
 use crate::config::{db::DB, env::Env};
 use crate::core::errors::AppError;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
 use crate::core::middleware::jwt_auth::AuthUser;
 
-use super::schema::{UpdateMeBody, UpdateRoleBody};
+use super::schema::{ChangeEmailBody, ChangePasswordBody, ConfirmEmailBody, DeleteMeBody, UpdateMeBody, UpdateRoleBody};
 use super::service;
 
 // Helper type alias
@@ -38,6 +41,57 @@ pub async fn patch_me(
     Ok(Json(json!({ "ok": true, "data": u })))
 }
 
+// POST /api/users/me/password
+pub async fn change_password(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    Extension(client): Extension<ApiClient>,
+    device: Device,
+    headers: HeaderMap,
+    Json(body): Json<ChangePasswordBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::change_password(&db, &env, &user, &client, &device, accept_language(&headers), body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+fn accept_language(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok())
+}
+
+// POST /api/users/me/email
+pub async fn request_email_change(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(body): Json<ChangeEmailBody>,
+) -> Result<Json<Value>, AppError> {
+    let out = service::request_email_change(&db, &env, &user, accept_language(&headers), body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
+// POST /api/users/me/email/confirm
+pub async fn confirm_email_change(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(body): Json<ConfirmEmailBody>,
+) -> Result<Json<Value>, AppError> {
+    let u = service::confirm_email_change(&db, &env, &user, accept_language(&headers), body).await?;
+    Ok(Json(json!({ "ok": true, "data": u })))
+}
+
+// DELETE /api/users/me (body เป็น optional: บัญชี OAuth อย่างเดียวไม่ต้องส่ง password)
+pub async fn delete_me(
+    State((db, env)): AppState,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    body: Option<Json<DeleteMeBody>>,
+) -> Result<Json<Value>, AppError> {
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let out = service::request_deletion(&db, &env, &user, accept_language(&headers), body).await?;
+    Ok(Json(json!({ "ok": true, "data": out })))
+}
+
 // --------------------
 // Admin (existing)
 // --------------------
@@ -51,9 +105,28 @@ pub async fn list_users(State((db, _)): AppState) -> Result<Json<Value>, AppErro
 // PATCH /api/users/:id/role
 pub async fn update_role(
     State((db, _)): AppState,
+    Extension(user): Extension<AuthUser>,
     Path(id): Path<i32>,
     Json(body): Json<UpdateRoleBody>,
 ) -> Result<Json<Value>, AppError> {
-    let user = service::update_role(&db, id, body.role).await?;
+    let user = service::update_role(&db, &user, id, body.role).await?;
     Ok(Json(json!({ "ok": true, "data": user })))
 }
+
+// POST /api/users/:id/kick
+pub async fn kick_user(
+    State((db, _)): AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::kick_user(&db, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+// POST /api/users/:id/unlock
+pub async fn unlock_user(
+    State((db, _)): AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<Value>, AppError> {
+    service::unlock_user(&db, id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
@@ -1,4 +1,4 @@
-use axum::{middleware, routing::{get, patch}, Router};
+use axum::{middleware, routing::{delete, get, patch, post}, Router};
 
 use crate::config::{db::DB, env::Env};
 use crate::core::middleware::jwt_auth;
@@ -12,13 +12,48 @@ pub fn routes(db: DB, env: Env) -> Router {
         .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
         .with_state((db.clone(), env.clone()));
 
-    // admin endpoints (jwt + admin)
+    // เปลี่ยนข้อมูลเข้าสู่ระบบ / ลบบัญชี (เจ้าของบัญชีเท่านั้น admin สวมรอย / PAT ทำไม่ได้)
+    let credential_routes = Router::new()
+        .route("/me", delete(controller::delete_me))
+        .route("/me/password", post(controller::change_password))
+        .route("/me/email", post(controller::request_email_change))
+        .route("/me/email/confirm", post(controller::confirm_email_change))
+        .route_layer(middleware::from_fn(jwt_auth::mw_deny_delegated))
+        .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
+        .with_state((db.clone(), env.clone()));
+
+    // admin endpoints (jwt + users:manage)
     let admin_routes = Router::new()
         .route("/", get(controller::list_users))
         .route("/:id/role", patch(controller::update_role))
-        .route_layer(middleware::from_fn(jwt_auth::mw_require_admin))
+        .route("/:id/kick", post(controller::kick_user))
+        .route("/:id/unlock", post(controller::unlock_user))
+        .route_layer(middleware::from_fn(jwt_auth::mw_require_permission("users:manage")))
         .route_layer(middleware::from_fn(jwt_auth::mw_jwt_auth))
-        .with_state((db, env));
+        .with_state((db.clone(), env.clone()));
+
+    // บัญชี OAuth/OIDC ที่ผูกไว้
+    let identity_routes = crate::api::identities::routes::routes(db.clone(), env.clone());
+
+    // device ที่ login อยู่ (ของตัวเอง + admin ดู/ปิดของ user อื่น)
+    let session_routes = crate::api::sessions::routes::routes(db.clone(), env.clone());
+    let admin_session_routes = crate::api::sessions::routes::admin_routes(db.clone(), env.clone());
+
+    // personal access token (Bearer pat_...)
+    let token_routes = crate::api::tokens::routes::routes(db.clone(), env.clone());
+
+    // admin สวมรอยเป็น user (support) + หยุดสวมรอย
+    let impersonation_routes = crate::api::impersonation::routes::routes(db.clone(), env.clone());
+    let admin_impersonation_routes = crate::api::impersonation::routes::admin_routes(db.clone(), env.clone());
 
-    Router::new().merge(me_routes).merge(admin_routes)
+    Router::new()
+        .merge(me_routes)
+        .merge(credential_routes)
+        .nest("/me/identities", identity_routes)
+        .nest("/me/sessions", session_routes)
+        .nest("/me/tokens", token_routes)
+        .nest("/me/impersonation", impersonation_routes)
+        .merge(admin_routes)
+        .merge(admin_session_routes)
+        .merge(admin_impersonation_routes)
 }
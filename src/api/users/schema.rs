@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,6 +20,7 @@ pub struct UserMeRow {
     pub role: String,
     pub profile_picture_url: Option<String>,
     pub is_email_verified: bool,
+    pub locale: Option<String>,
 }
 
 // ✅ pure-api1 compatibility: PATCH /api/users/me body
@@ -26,6 +28,52 @@ pub struct UserMeRow {
 pub struct UpdateMeBody {
     pub username: Option<String>,
     pub profile_picture_url: Option<String>,
+    // ภาษาของอีเมล: "en" | "th"
+    pub locale: Option<String>,
+}
+
+// POST /api/users/me/password (บัญชี OAuth อย่างเดียวไม่ต้องส่ง currentPassword แต่ต้อง login มาไม่นาน)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordBody {
+    #[serde(alias = "current_password")]
+    pub current_password: Option<String>,
+    #[serde(alias = "new_password")]
+    pub new_password: String,
+}
+
+// POST /api/users/me/email : ขอเปลี่ยนอีเมล (ส่งรหัสไปอีเมลใหม่)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEmailBody {
+    #[serde(alias = "new_email")]
+    pub new_email: String,
+    pub password: Option<String>,
+}
+
+// POST /api/users/me/email/confirm
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailBody {
+    pub code: String,
+}
+
+// คำขอเปลี่ยนอีเมลที่รอยืนยัน
+#[derive(Debug, Serialize)]
+pub struct PendingEmailChange {
+    pub new_email: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+// DELETE /api/users/me (บัญชี OAuth อย่างเดียวไม่ต้องส่ง password แต่ต้อง login มาไม่นาน)
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteMeBody {
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletionScheduled {
+    // ลบจริงตอนนี้ login ก่อนหน้านั้น = ยกเลิก
+    pub delete_at: DateTime<Utc>,
 }
 
 // ✅ ใช้สำหรับรับค่า JSON ตอนเปลี่ยน Role ในหน้า Admin
@@ -1,9 +1,23 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use sqlx::Row;
 
-use crate::config::db::DB;
+use crate::api::auth::{reauth, schema::AuthResponse, service as auth_service};
+use crate::config::{db::DB, env::Env};
 use crate::core::errors::AppError;
+use crate::core::mail::{self, templates::{Locale, Template}};
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::middleware::device::Device;
+use crate::core::middleware::jwt_auth::AuthUser;
+use crate::core::utils::{password, password_policy, rbac, revocation, token_hash};
 
-use super::schema::{UpdateMeBody, UserMeRow, UserRow};
+use super::schema::{
+    ChangeEmailBody, ChangePasswordBody, ConfirmEmailBody, DeleteMeBody, DeletionScheduled, PendingEmailChange, UpdateMeBody,
+    UserMeRow, UserRow,
+};
+
+// ใส่รหัสเปลี่ยนอีเมลผิดได้กี่ครั้งต่อคำขอ ครบแล้วต้องขอรหัสใหม่
+const EMAIL_CODE_MAX_ATTEMPTS: i32 = 5;
 
 pub async fn list_users(db: &DB) -> Result<Vec<UserRow>, AppError> {
     let rows = sqlx::query(
@@ -28,7 +42,36 @@ pub async fn list_users(db: &DB) -> Result<Vec<UserRow>, AppError> {
 }
 
 /// Admin: PATCH /api/users/:id/role
-pub async fn update_role(db: &DB, id: i32, role: String) -> Result<UserRow, AppError> {
+///
+/// ให้ / ถอดได้เฉพาะ role ที่ permission ไม่เกินของตัวเอง เปลี่ยน role ตัวเองไม่ได้
+/// เปลี่ยนแล้ว revoke token ทั้งหมดของ user นั้น (role ใน token เก่าใช้ต่อไม่ได้)
+pub async fn update_role(db: &DB, caller: &AuthUser, id: i32, role: String) -> Result<UserRow, AppError> {
+    if id == caller.id {
+        return Err(AppError::forbidden("ROLE_SELF", "You cannot change your own role"));
+    }
+
+    // role ต้องมีอยู่ใน roles (ดู /api/admin/roles)
+    let role = role.trim().to_lowercase();
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM roles WHERE name = $1)")
+        .bind(&role)
+        .fetch_one(&db.pool)
+        .await?;
+    if !exists {
+        return Err(AppError::bad_request(format!("Unknown role: {}", role)));
+    }
+
+    let current: String = sqlx::query_scalar("SELECT role FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    for r in [&current, &role] {
+        let missing = rbac::missing_from(db, &caller.role, r).await?;
+        if !missing.is_empty() {
+            return Err(rbac::escalation(missing));
+        }
+    }
+
     let row = sqlx::query(
         r#"
         UPDATE users
@@ -38,10 +81,15 @@ pub async fn update_role(db: &DB, id: i32, role: String) -> Result<UserRow, AppE
         "#,
     )
     .bind(id)
-    .bind(role)
+    .bind(&role)
     .fetch_optional(&db.pool)
     .await?;
 
+    if row.is_some() && current != role {
+        revocation::revoke_user(db, id, "role_changed").await?;
+        tracing::warn!("🛡️ user {} role {} -> {} by {}", id, current, role, caller.id);
+    }
+
     match row {
         Some(r) => Ok(UserRow {
             id: r.get("id"),
@@ -55,11 +103,38 @@ pub async fn update_role(db: &DB, id: i32, role: String) -> Result<UserRow, AppE
     }
 }
 
+/// Admin: POST /api/users/:id/kick (บังคับ logout ทุก device)
+pub async fn kick_user(db: &DB, id: i32) -> Result<(), AppError> {
+    let exists = sqlx::query("SELECT id FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&db.pool)
+        .await?;
+
+    if exists.is_none() {
+        return Err(AppError::not_found("USER_NOT_FOUND", "User not found"));
+    }
+
+    revocation::revoke_user(db, id, "kicked").await
+}
+
+/// Admin: POST /api/users/:id/unlock (ปลดล็อกจากการ login ผิดเกินกำหนด)
+pub async fn unlock_user(db: &DB, id: i32) -> Result<(), AppError> {
+    let res = sqlx::query("UPDATE users SET failed_login_attempts = 0, locked_until = NULL, updated_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&db.pool)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(AppError::not_found("USER_NOT_FOUND", "User not found"));
+    }
+    Ok(())
+}
+
 /// pure-api1: GET /api/users/me
 pub async fn get_by_id(db: &DB, id: i32) -> Result<UserMeRow, AppError> {
     let row = sqlx::query(
         r#"
-        SELECT id, username, email, role, profile_picture_url, is_email_verified
+        SELECT id, username, email, role, profile_picture_url, is_email_verified, locale
         FROM users
         WHERE id = $1
         "#,
@@ -76,6 +151,7 @@ pub async fn get_by_id(db: &DB, id: i32) -> Result<UserMeRow, AppError> {
             role: r.get("role"),
             profile_picture_url: r.get("profile_picture_url"),
             is_email_verified: r.get("is_email_verified"),
+            locale: r.get("locale"),
         }),
         None => Err(AppError::not_found("USER_NOT_FOUND", "User not found")),
     }
@@ -83,19 +159,26 @@ pub async fn get_by_id(db: &DB, id: i32) -> Result<UserMeRow, AppError> {
 
 /// pure-api1: PATCH /api/users/me
 pub async fn update_me(db: &DB, id: i32, body: UpdateMeBody) -> Result<UserMeRow, AppError> {
+    let locale = match body.locale.as_deref() {
+        Some(v) => Some(Locale::parse(v).ok_or_else(|| AppError::bad_request("Unsupported locale"))?.as_str()),
+        None => None,
+    };
+
     let row = sqlx::query(
         r#"
         UPDATE users
         SET username = COALESCE($2, username),
             profile_picture_url = COALESCE($3, profile_picture_url),
+            locale = COALESCE($4, locale),
             updated_at = NOW()
         WHERE id = $1
-        RETURNING id, username, email, role, profile_picture_url, is_email_verified
+        RETURNING id, username, email, role, profile_picture_url, is_email_verified, locale
         "#,
     )
     .bind(id)
     .bind(body.username)
     .bind(body.profile_picture_url)
+    .bind(locale)
     .fetch_optional(&db.pool)
     .await?;
 
@@ -107,7 +190,340 @@ pub async fn update_me(db: &DB, id: i32, body: UpdateMeBody) -> Result<UserMeRow
             role: r.get("role"),
             profile_picture_url: r.get("profile_picture_url"),
             is_email_verified: r.get("is_email_verified"),
+            locale: r.get("locale"),
         }),
         None => Err(AppError::not_found("USER_NOT_FOUND", "User not found")),
     }
 }
+
+/// POST /api/users/me/password : เปลี่ยนรหัสผ่าน แล้ว revoke token เดิมทุกใบ (ทุกเครื่อง)
+/// คืน token คู่ใหม่ให้เครื่องที่เปลี่ยน จะได้ไม่หลุด login
+pub async fn change_password(
+    db: &DB,
+    env: &Env,
+    user: &AuthUser,
+    client: &ApiClient,
+    device: &Device,
+    accept_language: Option<&str>,
+    body: ChangePasswordBody,
+) -> Result<AuthResponse, AppError> {
+    reauth::require(db, env, user, body.current_password.as_deref()).await?;
+
+    let row = sqlx::query("SELECT email, username, locale FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    let email: String = row.get("email");
+    let username: Option<String> = row.get("username");
+    let user_locale: Option<String> = row.get("locale");
+
+    password_policy::check(&body.new_password, Some(&email), username.as_deref())?;
+    let pw_hash = password::hash_password(&body.new_password).await?;
+
+    sqlx::query("UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+        .bind(user.id)
+        .bind(pw_hash)
+        .execute(&db.pool)
+        .await?;
+
+    revocation::revoke_user(db, user.id, "password_change").await?;
+    let out = auth_service::issue_for_user(db, env, client, device, user.id).await?;
+
+    let locale = Locale::resolve(user_locale.as_deref(), accept_language);
+    mail::queue(Template::PasswordChanged.render(&email, locale, &env.app_name));
+    Ok(out)
+}
+
+// --- Email Change ---
+
+const EMAIL_CHANGE_MINUTES: i64 = 15;
+
+/// อีเมลนี้มีเจ้าของจริงแล้วหรือยัง
+/// user ที่ยังไม่ยืนยัน/ไม่มีรหัสผ่าน/ไม่ได้ผูก OAuth (ค้างจาก register หรือ magic link) ไม่นับ
+async fn email_taken<'e, E>(exec: E, email: &str, user_id: i32) -> Result<bool, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let taken: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM users u
+            WHERE LOWER(u.email) = $1 AND u.id <> $2
+              AND (u.is_email_verified OR u.password_hash IS NOT NULL
+                   OR EXISTS (SELECT 1 FROM user_identities i WHERE i.user_id = u.id))
+        )
+        "#,
+    )
+    .bind(email)
+    .bind(user_id)
+    .fetch_one(exec)
+    .await?;
+    Ok(taken)
+}
+
+/// ตอน confirm: มีเจ้าของจริงไปก่อนระหว่างรอ -> 409, ค้างแค่บัญชีที่ยังไม่ยืนยัน -> ลบทิ้ง (เกณฑ์เดียวกับ email_taken)
+async fn claim_email(conn: &mut sqlx::PgConnection, email: &str, user_id: i32) -> Result<(), AppError> {
+    if email_taken(&mut *conn, email, user_id).await? {
+        return Err(AppError::conflict("EMAIL_EXISTS", "Email already registered"));
+    }
+    sqlx::query(
+        r#"
+        DELETE FROM users u
+        WHERE LOWER(u.email) = $1 AND u.id <> $2
+          AND NOT u.is_email_verified AND u.password_hash IS NULL
+          AND NOT EXISTS (SELECT 1 FROM user_identities i WHERE i.user_id = u.id)
+        "#,
+    )
+    .bind(email)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// POST /api/users/me/email : ยืนยันตัวตน แล้วส่งรหัสไปที่อีเมลใหม่ (อีเมลยังไม่เปลี่ยนจนกว่าจะ confirm)
+pub async fn request_email_change(
+    db: &DB,
+    env: &Env,
+    user: &AuthUser,
+    accept_language: Option<&str>,
+    body: ChangeEmailBody,
+) -> Result<PendingEmailChange, AppError> {
+    let new_email = body.new_email.trim().to_lowercase();
+    let valid = new_email
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.starts_with('.'));
+    if !valid || new_email.len() > 255 {
+        return Err(AppError::bad_request("Invalid email"));
+    }
+
+    reauth::require(db, env, user, body.password.as_deref()).await?;
+
+    let row = sqlx::query("SELECT email, locale FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    let current: String = row.get("email");
+    let user_locale: Option<String> = row.get("locale");
+
+    if current.eq_ignore_ascii_case(&new_email) {
+        return Err(AppError::bad_request("New email is the same as the current email"));
+    }
+    if email_taken(&db.pool, &new_email, user.id).await? {
+        return Err(AppError::conflict("EMAIL_EXISTS", "Email already registered"));
+    }
+
+    let code: String = rand::thread_rng().gen_range(100000..999999).to_string();
+    let r = sqlx::query(
+        r#"
+        INSERT INTO email_change_requests (user_id, new_email, code_hash, expires_at)
+        VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))
+        ON CONFLICT (user_id) DO UPDATE
+        SET new_email = EXCLUDED.new_email, code_hash = EXCLUDED.code_hash,
+            expires_at = EXCLUDED.expires_at, attempts = 0, created_at = NOW()
+        RETURNING new_email, expires_at
+        "#,
+    )
+    .bind(user.id)
+    .bind(&new_email)
+    .bind(token_hash::hash_token(&code))
+    .bind(EMAIL_CHANGE_MINUTES as i32)
+    .fetch_one(&db.pool)
+    .await?;
+
+    let locale = Locale::resolve(user_locale.as_deref(), accept_language);
+    mail::queue(Template::EmailChangeCode { code: &code, minutes: EMAIL_CHANGE_MINUTES }.render(&new_email, locale, &env.app_name));
+
+    Ok(PendingEmailChange { new_email: r.get("new_email"), expires_at: r.get("expires_at") })
+}
+
+/// POST /api/users/me/email/confirm : รหัสถูก -> เปลี่ยนอีเมล แล้วแจ้งไปที่อีเมลเดิม
+///
+/// OAuth identity ผูกด้วย (provider, subject) ไม่ใช่อีเมล จึงยัง login ด้วย provider เดิมได้
+pub async fn confirm_email_change(
+    db: &DB,
+    env: &Env,
+    user: &AuthUser,
+    accept_language: Option<&str>,
+    body: ConfirmEmailBody,
+) -> Result<UserMeRow, AppError> {
+    let hashed = token_hash::hash_token(body.code.trim());
+
+    // นับครั้งก่อนเทียบ (นอก transaction ให้ผิดแล้วนับจริง) รหัส 6 หลักเดาได้ถ้าไม่จำกัด
+    let attempt = sqlx::query(
+        r#"
+        UPDATE email_change_requests SET attempts = attempts + 1
+        WHERE user_id = $1 AND expires_at > NOW() AND attempts < $2
+        RETURNING code_hash = $3 AS matched, attempts
+        "#,
+    )
+    .bind(user.id)
+    .bind(EMAIL_CODE_MAX_ATTEMPTS)
+    .bind(&hashed)
+    .fetch_optional(&db.pool)
+    .await?;
+    let Some(attempt) = attempt else {
+        return Err(AppError::bad_request("Invalid or expired code"));
+    };
+    if !attempt.get::<bool, _>("matched") {
+        let attempts_left = EMAIL_CODE_MAX_ATTEMPTS - attempt.get::<i32, _>("attempts");
+        if attempts_left <= 0 {
+            sqlx::query("DELETE FROM email_change_requests WHERE user_id = $1")
+                .bind(user.id)
+                .execute(&db.pool)
+                .await?;
+            return Err(AppError::bad_request("Too many wrong codes, request a new one"));
+        }
+        return Err(AppError::bad_request("Invalid or expired code")
+            .with_details(serde_json::json!({ "attempts_left": attempts_left })));
+    }
+
+    let mut tx = db.pool.begin().await?;
+
+    let pending = sqlx::query(
+        "DELETE FROM email_change_requests WHERE user_id = $1 AND code_hash = $2 AND expires_at > NOW() RETURNING new_email",
+    )
+    .bind(user.id)
+    .bind(&hashed)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(pending) = pending else {
+        return Err(AppError::bad_request("Invalid or expired code"));
+    };
+    let new_email: String = pending.get("new_email");
+
+    claim_email(&mut tx, &new_email, user.id).await?;
+
+    let old = sqlx::query("SELECT email FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+    let old_email: String = old.get("email");
+
+    let r = sqlx::query(
+        r#"
+        UPDATE users
+        SET email = $2, is_email_verified = TRUE, updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, username, email, role, profile_picture_url, is_email_verified, locale
+        "#,
+    )
+    .bind(user.id)
+    .bind(&new_email)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(d) if d.is_unique_violation() => {
+            AppError::conflict("EMAIL_EXISTS", "Email already registered")
+        }
+        _ => AppError::from(e),
+    })?;
+
+    tx.commit().await?;
+
+    let out = UserMeRow {
+        id: r.get("id"),
+        username: r.get("username"),
+        email: r.get("email"),
+        role: r.get("role"),
+        profile_picture_url: r.get("profile_picture_url"),
+        is_email_verified: r.get("is_email_verified"),
+        locale: r.get("locale"),
+    };
+    let locale = Locale::resolve(out.locale.as_deref(), accept_language);
+    mail::queue(Template::EmailChanged { new_email: &new_email }.render(&old_email, locale, &env.app_name));
+    Ok(out)
+}
+
+/// DELETE /api/users/me : ยืนยันตัวตน แล้วนัดลบบัญชีหลังช่วงผ่อนผัน (ACCOUNT_DELETION_GRACE_SECS)
+/// logout ทุกเครื่องทันที ถ้า login กลับมาก่อนถึงเวลาจะยกเลิกการลบ (ดู auth::service::issue_tokens)
+pub async fn request_deletion(
+    db: &DB,
+    env: &Env,
+    user: &AuthUser,
+    accept_language: Option<&str>,
+    body: DeleteMeBody,
+) -> Result<DeletionScheduled, AppError> {
+    reauth::require(db, env, user, body.password.as_deref()).await?;
+
+    // ขอซ้ำไม่เลื่อนวันลบออกไป
+    let r = sqlx::query(
+        r#"
+        UPDATE users
+        SET deletion_scheduled_at = COALESCE(deletion_scheduled_at, NOW() + make_interval(secs => $2)), updated_at = NOW()
+        WHERE id = $1
+        RETURNING email, locale, deletion_scheduled_at
+        "#,
+    )
+    .bind(user.id)
+    .bind(env.account_deletion_grace_secs as f64)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("USER_NOT_FOUND", "User not found"))?;
+
+    let email: String = r.get("email");
+    let user_locale: Option<String> = r.get("locale");
+    let delete_at: DateTime<Utc> = r.get("deletion_scheduled_at");
+
+    revocation::revoke_user(db, user.id, "account_deletion").await?;
+    tracing::info!("user {} scheduled account deletion at {}", user.id, delete_at);
+
+    let locale = Locale::resolve(user_locale.as_deref(), accept_language);
+    let date = delete_at.format("%Y-%m-%d").to_string();
+    mail::queue(Template::AccountDeletionScheduled { date: &date }.render(&email, locale, &env.app_name));
+    Ok(DeletionScheduled { delete_at })
+}
+
+// ต้องมี Postgres ที่รัน db.sql แล้ว: DATABASE_URL=... cargo test -- --ignored
+// ทำใน transaction แล้ว rollback ทิ้ง ไม่เหลืออะไรใน DB
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn tx() -> sqlx::Transaction<'static, sqlx::Postgres> {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        DB::connect(&url).await.unwrap().pool.begin().await.unwrap()
+    }
+
+    async fn insert_user(conn: &mut sqlx::PgConnection, email: &str, verified: bool, password_hash: Option<&str>) -> i32 {
+        sqlx::query_scalar("INSERT INTO users (email, role, is_email_verified, password_hash) VALUES ($1, 'user', $2, $3) RETURNING id")
+            .bind(email)
+            .bind(verified)
+            .bind(password_hash)
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    }
+
+    async fn count(conn: &mut sqlx::PgConnection, email: &str) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE email = $1").bind(email).fetch_one(conn).await.unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn stale_stub_does_not_block_email_change() {
+        let mut tx = tx().await;
+        let me = insert_user(&mut tx, "me@email-change.test", true, Some("x")).await;
+        // register แล้วไม่ได้ยืนยัน
+        insert_user(&mut tx, "stub@email-change.test", false, None).await;
+
+        assert!(!email_taken(&mut *tx, "stub@email-change.test", me).await.unwrap());
+        claim_email(&mut tx, "stub@email-change.test", me).await.unwrap();
+        assert_eq!(count(&mut tx, "stub@email-change.test").await, 0);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn real_owner_conflicts_at_confirm() {
+        let mut tx = tx().await;
+        let me = insert_user(&mut tx, "me@email-change.test", true, Some("x")).await;
+        insert_user(&mut tx, "taken@email-change.test", false, Some("x")).await;
+
+        let err = claim_email(&mut tx, "taken@email-change.test", me).await.unwrap_err();
+        assert!(matches!(err, AppError::Http { code, .. } if code == "EMAIL_EXISTS"));
+        assert_eq!(count(&mut tx, "taken@email-change.test").await, 1);
+    }
+}
@@ -1,23 +1,79 @@
 use serde::{Deserialize, Serialize};
 use std::{env, sync::OnceLock};
 
+use super::oauth::{self, OAuthProvider};
+use super::password_policy::{self, PasswordPolicy};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Env {
+    pub app_name: String,
+    pub node_env: String,
     pub port: u16,
     pub database_url: String,
 
     pub jwt_secret: String,
     pub jwt_expires_in: String,
+    pub jwt_refresh_expires_in: String,
+    // iss ใน access token
+    pub jwt_issuer: String,
+    // key คู่ (RS256/EdDSA) สำหรับเซ็น access token ว่าง = ใช้ JWT_SECRET (HS256) เหมือนเดิม
+    pub jwt_signing_key_file: String,
+    // public/private key เก่าที่ยังต้องตรวจ token ได้ระหว่างหมุน key
+    pub jwt_verify_key_files: Vec<String>,
 
     pub google_client_id: String,
     pub google_client_secret: String,
     pub google_redirect_uri: String,
+    // provider registry สำหรับ /api/auth/oauth/:provider (รวม google)
+    pub oauth_providers: Vec<OAuthProvider>,
+    pub oauth_redirect_urls: Vec<String>,
 
     pub allowed_origins: Vec<String>,
+    // IP / CIDR ของ reverse proxy ที่เชื่อ X-Forwarded-For ได้ (ดู core::middleware::device)
+    pub trusted_proxies: Vec<String>,
+    pub frontend_urls: Vec<String>,
+    pub webauthn_rp_id: String,
+    pub reset_url_base: String,
+    pub magic_link_url_base: String,
+    pub magic_link_ttl_secs: i64,
     pub rate_limit_auth_max: u64, // เพิ่ม field นี้
 
+    // ล็อกบัญชีเมื่อ login ผิดติดกัน (ต่อ account ไม่ใช่ต่อ IP)
+    pub login_max_attempts: i32,
+    pub login_lockout_secs: i64,
+    pub login_lockout_max_secs: i64,
+    // งานสำคัญ (ผูกบัญชี ฯลฯ) ของ user ที่ไม่มีรหัสผ่าน ต้อง login มาไม่เกินกี่วินาที
+    pub reauth_max_age_secs: usize,
+    // อายุ token ตอน admin สวมรอยเป็น user (ไม่มี refresh token)
+    pub impersonation_ttl_secs: usize,
+    // ขอลบบัญชีแล้ว รอกี่วินาทีก่อนลบจริง (login ในช่วงนี้ = ยกเลิก)
+    pub account_deletion_grace_secs: i64,
+
+    // รหัสยืนยันอีเมล: ผิดได้กี่ครั้ง / ขอใหม่ได้ทุกกี่วินาที / กี่ครั้งต่อวันต่ออีเมล
+    pub verify_code_max_attempts: i32,
+    pub verify_code_resend_cooldown_secs: i64,
+    pub verify_code_daily_cap: i64,
+
+    pub password_policy: PasswordPolicy,
+    // hash รหัสผ่านใหม่ด้วย algorithm นี้ (hash เก่าจะถูก hash ใหม่ตอน login)
+    pub password_hash_algorithm: String,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub bcrypt_cost: u32,
+
     pub download_windows_path: String,
     pub download_android_path: String,
+
+    // Email (smtp = ส่งจริง, outbox = เขียนลงไฟล์สำหรับ dev/QA)
+    pub mail_backend: String,
+    pub mail_from: String,
+    pub mail_outbox_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub smtp_secure: String,
 }
 
 pub static ENV: OnceLock<Env> = OnceLock::new();
@@ -31,15 +87,40 @@ impl Env {
             .and_then(|v| v.parse().ok())
             .unwrap_or(5000); // แก้ default เป็น 5000 ให้ตรง pure-api
 
+        // ใช้เป็น issuer ใน authenticator app (2FA)
+        let app_name = env::var("APP_NAME").unwrap_or_else(|_| "pure-api".into());
+
+        let node_env = env::var("NODE_ENV").unwrap_or_else(|_| "development".into());
+
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
         let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-        let jwt_expires_in = env::var("JWT_EXPIRES_IN").unwrap_or_else(|_| "30d".into());
+        // access token อายุสั้น ส่วน refresh token อายุยาว (หมุนใหม่ทุกครั้งที่ใช้)
+        let jwt_expires_in = env::var("JWT_EXPIRES_IN").unwrap_or_else(|_| "15m".into());
+        let jwt_refresh_expires_in =
+            env::var("JWT_REFRESH_EXPIRES_IN").unwrap_or_else(|_| "30d".into());
+        let jwt_issuer = env::var("JWT_ISSUER").unwrap_or_else(|_| app_name.clone());
+        let jwt_signing_key_file = env::var("JWT_SIGNING_KEY_FILE").unwrap_or_default().trim().to_string();
+        let jwt_verify_key_files = env::var("JWT_VERIFY_KEY_FILES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
 
         let google_client_id = env::var("GOOGLE_CLIENT_ID").unwrap_or_default();
         let google_client_secret = env::var("GOOGLE_CLIENT_SECRET").unwrap_or_default();
         let google_redirect_uri = env::var("GOOGLE_REDIRECT_URI")
             .unwrap_or_else(|_| format!("http://localhost:{}/api/auth/oauth/google/callback", port));
+        let oauth_providers =
+            oauth::load_providers(port, &google_client_id, &google_client_secret, &google_redirect_uri);
+
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
 
         let allowed_origins = env::var("ALLOWED_ORIGINS")
             .unwrap_or_default()
@@ -48,32 +129,199 @@ impl Env {
             .filter(|s| !s.is_empty())
             .collect::<Vec<_>>();
 
+        // origin ของเว็บ frontend (React/Angular) ใช้เป็น origin ที่ passkey ยอมรับ
+        let frontend_urls = env::var("FRONTEND_URL")
+            .unwrap_or_else(|_| "http://localhost:3000".into())
+            .split(',')
+            .map(|s| s.trim().trim_end_matches('/').to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+
+        // ปลายทางหลัง OAuth callback (?code=...) ตัวแรก = default, ตัวอื่นต้องขอผ่าน ?redirect_uri=
+        let oauth_redirect_urls = env::var("OAUTH_REDIRECT_URLS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| {
+                vec![format!(
+                    "{}/oauth/callback",
+                    frontend_urls.first().map(String::as_str).unwrap_or("http://localhost:3000")
+                )]
+            });
+
+        // ว่าง = ใช้ host ของ FRONTEND_URL ตัวแรก
+        let webauthn_rp_id = env::var("WEBAUTHN_RP_ID").unwrap_or_default();
+
+        // หน้าเว็บตั้งรหัสผ่านใหม่ (ลิงก์ในอีเมลจะต่อ ?token=...)
+        let reset_url_base = env::var("RESET_URL_BASE")
+            .unwrap_or_else(|_| format!("http://localhost:{}/reset.html", port));
+
+        // หน้าเว็บ/แอปที่รับ magic link (ลิงก์ในอีเมลจะต่อ ?token=...)
+        let magic_link_url_base = env::var("MAGIC_LINK_URL_BASE")
+            .unwrap_or_else(|_| format!("http://localhost:{}/magic-link.html", port));
+
+        // อายุ magic link (วินาที) ตั้งให้สั้นไว้
+        let magic_link_ttl_secs = env::var("MAGIC_LINK_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(10 * 60);
+
         // Default 30 ตาม pure-api config
         let rate_limit_auth_max = env::var("RATE_LIMIT_AUTH_MAX")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(30);
 
+        // ผิดครบ LOGIN_MAX_ATTEMPTS -> ล็อก LOGIN_LOCKOUT_SECS แล้วเพิ่มเป็น 2 เท่าทุกครั้งที่ผิดต่อ (ไม่เกิน MAX)
+        let login_max_attempts = env::var("LOGIN_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let login_lockout_secs = env::var("LOGIN_LOCKOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let login_lockout_max_secs = env::var("LOGIN_LOCKOUT_MAX_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60 * 60);
+
+        let reauth_max_age_secs = env::var("REAUTH_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5 * 60);
+
+        let impersonation_ttl_secs = env::var("IMPERSONATION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(15 * 60);
+
+        let account_deletion_grace_secs = env::var("ACCOUNT_DELETION_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v >= 0)
+            .unwrap_or(30 * 24 * 60 * 60);
+
+        let verify_code_max_attempts = env::var("VERIFY_CODE_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(5);
+        let verify_code_resend_cooldown_secs = env::var("VERIFY_CODE_RESEND_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let verify_code_daily_cap = env::var("VERIFY_CODE_DAILY_CAP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(10);
+
+        // ค่า default ของ argon2id ตาม OWASP (19 MiB, 2 รอบ, 1 thread)
+        let password_hash_algorithm = match env::var("PASSWORD_HASH_ALGORITHM").as_deref() {
+            Ok("bcrypt") => "bcrypt".to_string(),
+            _ => "argon2id".to_string(),
+        };
+        let argon2_memory_kib = env::var("ARGON2_MEMORY_KIB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(19 * 1024);
+        let argon2_iterations = env::var("ARGON2_ITERATIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+        let argon2_parallelism = env::var("ARGON2_PARALLELISM")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+        let bcrypt_cost = env::var("BCRYPT_COST")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(bcrypt::DEFAULT_COST);
+
         let download_windows_path =
             env::var("DOWNLOAD_WINDOWS_PATH").unwrap_or_else(|_| "./app/MyAppSetup.exe".into());
         let download_android_path =
             env::var("DOWNLOAD_ANDROID_PATH").unwrap_or_else(|_| "./app/app-release.apk".into());
 
+        // production ส่งอีเมลจริงเป็นค่า default, dev เก็บลง outbox
+        let mail_backend = env::var("MAIL_BACKEND").unwrap_or_else(|_| {
+            if node_env == "production" { "smtp".into() } else { "outbox".into() }
+        });
+        let mail_from = env::var("MAIL_FROM").unwrap_or_else(|_| format!("{} <no-reply@localhost>", app_name));
+        let mail_outbox_dir = env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "./outbox".into());
+        let smtp_host = env::var("SMTP_HOST").unwrap_or_default();
+        let smtp_port = env::var("SMTP_PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(587);
+        let smtp_username = env::var("SMTP_USERNAME").unwrap_or_default();
+        let smtp_password = env::var("SMTP_PASSWORD").unwrap_or_default();
+        let smtp_secure = env::var("SMTP_SECURE").unwrap_or_else(|_| "starttls".into());
+
         let loaded = Env {
+            app_name,
+            node_env,
             port,
             database_url,
             jwt_secret,
             jwt_expires_in,
+            jwt_refresh_expires_in,
+            jwt_issuer,
+            jwt_signing_key_file,
+            jwt_verify_key_files,
             google_client_id,
             google_client_secret,
             google_redirect_uri,
+            oauth_providers,
+            oauth_redirect_urls,
             allowed_origins,
+            trusted_proxies,
+            frontend_urls,
+            webauthn_rp_id,
+            reset_url_base,
+            magic_link_url_base,
+            magic_link_ttl_secs,
             rate_limit_auth_max,
+            login_max_attempts,
+            login_lockout_secs,
+            login_lockout_max_secs,
+            reauth_max_age_secs,
+            impersonation_ttl_secs,
+            account_deletion_grace_secs,
+            verify_code_max_attempts,
+            verify_code_resend_cooldown_secs,
+            verify_code_daily_cap,
+            password_policy: password_policy::load(),
+            password_hash_algorithm,
+            argon2_memory_kib,
+            argon2_iterations,
+            argon2_parallelism,
+            bcrypt_cost,
             download_windows_path,
             download_android_path,
+            mail_backend,
+            mail_from,
+            mail_outbox_dir,
+            smtp_host,
+            smtp_port,
+            smtp_username,
+            smtp_password,
+            smtp_secure,
         };
 
         let _ = ENV.set(loaded.clone());
         loaded
     }
+
+    pub fn oauth_provider(&self, name: &str) -> Option<&OAuthProvider> {
+        self.oauth_providers.iter().find(|p| p.name == name)
+    }
 }
\ No newline at end of file
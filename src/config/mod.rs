@@ -1,3 +1,5 @@
 pub mod db;
 pub mod env;
+pub mod oauth;
+pub mod password_policy;
 pub mod pg;
\ No newline at end of file
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// ชื่อ claim ที่ใช้ดึงข้อมูล user (ID token หรือ userinfo) แต่ละ provider ตั้งชื่อไม่เหมือนกัน
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClaimMapping {
+    pub sub: String,
+    pub email: String,
+    pub email_verified: String,
+    pub picture: String,
+}
+
+/// 1 รายการใน provider registry (ตั้งผ่าน OAUTH_PROVIDERS + OAUTH_<NAME>_*)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OAuthProvider {
+    pub name: String,
+    // ตัวแรกใช้หา discovery ({issuer}/.well-known/openid-configuration), ว่าง = ไม่ใช่ OIDC (เช่น GitHub)
+    pub issuers: Vec<String>,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+    pub redirect_uri: String,
+    // ไม่ใส่ = ใช้ค่าจาก discovery
+    pub auth_url: Option<String>,
+    pub token_url: Option<String>,
+    pub jwks_uri: Option<String>,
+    pub userinfo_url: Option<String>,
+    pub claims: ClaimMapping,
+    // provider ยืนยันอีเมลให้แล้วแต่ไม่ส่ง email_verified มา
+    pub trust_email: bool,
+}
+
+fn var(name: &str, key: &str) -> Option<String> {
+    env::var(format!("OAUTH_{}_{}", name.to_uppercase(), key))
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn default_redirect_uri(port: u16, name: &str) -> String {
+    format!("http://localhost:{}/api/auth/oauth/{}/callback", port, name)
+}
+
+fn from_env(name: &str, port: u16) -> OAuthProvider {
+    let claim = |key: &str, default: &str| var(name, key).unwrap_or_else(|| default.to_string());
+    OAuthProvider {
+        name: name.to_string(),
+        issuers: var(name, "ISSUER")
+            .map(|v| v.split(',').map(|s| s.trim().trim_end_matches('/').to_string()).collect())
+            .unwrap_or_default(),
+        client_id: var(name, "CLIENT_ID").unwrap_or_default(),
+        client_secret: var(name, "CLIENT_SECRET").unwrap_or_default(),
+        scopes: claim("SCOPES", "openid email profile"),
+        redirect_uri: var(name, "REDIRECT_URI").unwrap_or_else(|| default_redirect_uri(port, name)),
+        auth_url: var(name, "AUTH_URL"),
+        token_url: var(name, "TOKEN_URL"),
+        jwks_uri: var(name, "JWKS_URI"),
+        userinfo_url: var(name, "USERINFO_URL"),
+        claims: ClaimMapping {
+            sub: claim("CLAIM_SUB", "sub"),
+            email: claim("CLAIM_EMAIL", "email"),
+            email_verified: claim("CLAIM_EMAIL_VERIFIED", "email_verified"),
+            picture: claim("CLAIM_PICTURE", "picture"),
+        },
+        trust_email: var(name, "TRUST_EMAIL").is_some_and(|v| v == "true" || v == "1"),
+    }
+}
+
+/// Google มาจาก GOOGLE_* เดิม (ถ้าไม่ได้ประกาศซ้ำใน OAUTH_PROVIDERS)
+fn google(client_id: &str, client_secret: &str, redirect_uri: &str) -> OAuthProvider {
+    OAuthProvider {
+        name: "google".into(),
+        issuers: vec!["https://accounts.google.com".into(), "accounts.google.com".into()],
+        client_id: client_id.to_string(),
+        client_secret: client_secret.to_string(),
+        scopes: "openid email profile".into(),
+        redirect_uri: redirect_uri.to_string(),
+        auth_url: Some(
+            env::var("GOOGLE_AUTH_URL")
+                .unwrap_or_else(|_| "https://accounts.google.com/o/oauth2/v2/auth".into()),
+        ),
+        token_url: Some(
+            env::var("GOOGLE_TOKEN_URL").unwrap_or_else(|_| "https://oauth2.googleapis.com/token".into()),
+        ),
+        // ใส่ file:/path/jwks.json เพื่อเทส offline
+        jwks_uri: Some(
+            env::var("GOOGLE_JWKS_URI")
+                .unwrap_or_else(|_| "https://www.googleapis.com/oauth2/v3/certs".into()),
+        ),
+        userinfo_url: None,
+        claims: ClaimMapping {
+            sub: "sub".into(),
+            email: "email".into(),
+            email_verified: "email_verified".into(),
+            picture: "picture".into(),
+        },
+        trust_email: false,
+    }
+}
+
+pub fn load_providers(
+    port: u16,
+    google_client_id: &str,
+    google_client_secret: &str,
+    google_redirect_uri: &str,
+) -> Vec<OAuthProvider> {
+    let mut out = Vec::new();
+
+    let names = env::var("OAUTH_PROVIDERS").unwrap_or_default();
+    for name in names.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()) {
+        // users.oauth_provider เป็น VARCHAR(20) และชื่อต้องอยู่ใน URL ได้
+        if name.len() > 20 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            tracing::warn!("Skipping OAuth provider with invalid name: {}", name);
+            continue;
+        }
+        let p = from_env(&name, port);
+        if p.client_id.is_empty() {
+            tracing::warn!("Skipping OAuth provider {}: OAUTH_{}_CLIENT_ID is not set", name, name.to_uppercase());
+            continue;
+        }
+        if p.issuers.is_empty() && (p.auth_url.is_none() || p.token_url.is_none()) {
+            tracing::warn!("Skipping OAuth provider {}: needs ISSUER or AUTH_URL + TOKEN_URL", name);
+            continue;
+        }
+        out.push(p);
+    }
+
+    if !google_client_id.is_empty() && !out.iter().any(|p| p.name == "google") {
+        out.push(google(google_client_id, google_client_secret, google_redirect_uri));
+    }
+
+    out
+}
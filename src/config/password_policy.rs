@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+// bcrypt ใช้แค่ 72 byte แรก ยาวกว่านี้ส่วนที่เกินจะถูกตัดทิ้งเงียบ ๆ
+pub const BCRYPT_MAX_BYTES: usize = 72;
+
+/// กติการหัสผ่าน (ตั้งผ่าน PASSWORD_*) ใช้กับทุกที่ที่ตั้ง/เปลี่ยนรหัสผ่าน
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // นับเป็น byte (UTF-8) ไม่เกิน BCRYPT_MAX_BYTES
+    pub max_bytes: usize,
+    pub require_lower: bool,
+    pub require_upper: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    // ไฟล์รหัสผ่านที่รั่ว บรรทัดละ 1 รหัส (ว่าง = ไม่เช็ค)
+    pub breached_list_file: Option<String>,
+}
+
+fn flag(key: &str) -> bool {
+    env::var(key).is_ok_and(|v| v == "true" || v == "1")
+}
+
+pub fn load() -> PasswordPolicy {
+    let min_length = env::var("PASSWORD_MIN_LENGTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8);
+    let max_bytes = env::var("PASSWORD_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(BCRYPT_MAX_BYTES)
+        .min(BCRYPT_MAX_BYTES);
+
+    PasswordPolicy {
+        min_length,
+        max_bytes,
+        require_lower: flag("PASSWORD_REQUIRE_LOWER"),
+        require_upper: flag("PASSWORD_REQUIRE_UPPER"),
+        require_digit: flag("PASSWORD_REQUIRE_DIGIT"),
+        require_symbol: flag("PASSWORD_REQUIRE_SYMBOL"),
+        breached_list_file: env::var("PASSWORD_BREACHED_LIST_FILE")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty()),
+    }
+}
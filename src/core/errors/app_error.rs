@@ -55,6 +55,33 @@ impl AppError {
     pub fn internal(message: impl Into<String>) -> Self {
         Self::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", message)
     }
+
+    /// login ผิดเกินกำหนด -> ล็อกชั่วคราว (client ใช้ retry_after แสดงเวลารอ)
+    pub fn account_locked(retry_after_secs: i64) -> Self {
+        Self::new(StatusCode::LOCKED, "ACCOUNT_LOCKED", "Account temporarily locked due to too many failed login attempts")
+            .with_details(json!({ "retry_after": retry_after_secs.max(1) }))
+    }
+
+    /// ถี่เกินไป (429) ใส่ retry_after ให้ client รู้ว่าต้องรอกี่วินาที
+    pub fn too_many_requests(code: impl Into<String>, message: impl Into<String>, retry_after_secs: Option<i64>) -> Self {
+        let err = Self::new(StatusCode::TOO_MANY_REQUESTS, code, message);
+        match retry_after_secs {
+            Some(secs) => err.with_details(json!({ "retry_after": secs.max(1) })),
+            None => err,
+        }
+    }
+
+    pub fn with_details(self, value: serde_json::Value) -> Self {
+        match self {
+            Self::Http { status, code, message, .. } => Self::Http {
+                status,
+                code,
+                message,
+                details: Some(value),
+            },
+            other => other,
+        }
+    }
 }
 
 impl IntoResponse for AppError {
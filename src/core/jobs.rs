@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use crate::config::db::DB;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// ตารางที่มีข้อมูลหมดอายุให้ล้างทิ้งเป็นรอบ ๆ (label, SQL)
+const PURGE_QUERIES: &[(&str, &str)] = &[
+    // token ตัวจริงหมดอายุแล้ว ไม่ต้องเก็บ denylist ต่อ
+    ("revoked_tokens", "DELETE FROM revoked_tokens WHERE expires_at <= NOW()"),
+    ("refresh_tokens", "DELETE FROM refresh_tokens WHERE expires_at <= NOW()"),
+    // session ที่ refresh token หมดอายุไปหมดแล้ว (ต้องอยู่หลัง refresh_tokens) / session สวมรอยที่หมดอายุ
+    (
+        "sessions",
+        "DELETE FROM sessions s WHERE CASE WHEN s.expires_at IS NULL \
+         THEN NOT EXISTS (SELECT 1 FROM refresh_tokens r WHERE r.family_id = s.family_id) \
+         ELSE s.expires_at <= NOW() END",
+    ),
+    ("mfa_challenges", "DELETE FROM mfa_challenges WHERE expires_at <= NOW()"),
+    ("webauthn_challenges", "DELETE FROM webauthn_challenges WHERE expires_at <= NOW()"),
+    ("oauth_states", "DELETE FROM oauth_states WHERE expires_at <= NOW()"),
+    ("oauth_login_codes", "DELETE FROM oauth_login_codes WHERE expires_at <= NOW()"),
+    ("magic_link_tokens", "DELETE FROM magic_link_tokens WHERE expires_at <= NOW()"),
+    ("verification_codes", "DELETE FROM verification_codes WHERE expires_at <= NOW()"),
+    ("verification_code_sends", "DELETE FROM verification_code_sends WHERE sent_at <= NOW() - INTERVAL '1 day'"),
+    ("email_change_requests", "DELETE FROM email_change_requests WHERE expires_at <= NOW()"),
+    ("personal_access_tokens", "DELETE FROM personal_access_tokens WHERE expires_at <= NOW() OR revoked_at IS NOT NULL"),
+    // บัญชีที่ขอลบและพ้นช่วงผ่อนผันแล้ว (ข้อมูลอื่นถูกลบตาม ON DELETE CASCADE)
+    ("users", "DELETE FROM users WHERE deletion_scheduled_at <= NOW()"),
+];
+
+async fn purge_expired(db: &DB) {
+    for (label, sql) in PURGE_QUERIES {
+        match sqlx::query(sql).execute(&db.pool).await {
+            Ok(res) if res.rows_affected() > 0 => {
+                tracing::info!("🧹 Purged {} expired rows from {}", res.rows_affected(), label)
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to purge {}: {}", label, e),
+        }
+    }
+}
+
+/// งานเบื้องหลังที่รันเป็นรอบ ๆ (ล้างข้อมูลหมดอายุ)
+pub fn spawn(db: DB) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            purge_expired(&db).await;
+        }
+    });
+}
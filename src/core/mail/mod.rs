@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::config::env::Env;
+
+pub mod outbox;
+pub mod smtp;
+pub mod templates;
+
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("Invalid address: {0}")]
+    Address(String),
+
+    #[error("Mail transport error: {0}")]
+    Transport(String),
+}
+
+/// backend สำหรับส่งอีเมล (SMTP จริง หรือ outbox สำหรับ dev)
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, msg: &EmailMessage) -> Result<(), MailError>;
+}
+
+static MAILER: OnceLock<Arc<dyn Mailer>> = OnceLock::new();
+
+/// เลือก backend ตาม MAIL_BACKEND (เรียกครั้งเดียวตอน start)
+pub fn init(env: &Env) -> Result<(), MailError> {
+    let mailer: Arc<dyn Mailer> = match env.mail_backend.as_str() {
+        "smtp" => Arc::new(smtp::SmtpMailer::new(env)?),
+        "outbox" => Arc::new(outbox::OutboxMailer::new(&env.mail_outbox_dir)),
+        other => return Err(MailError::Transport(format!("Unknown MAIL_BACKEND: {}", other))),
+    };
+
+    let _ = MAILER.set(mailer);
+    tracing::info!("📧 Mail backend: {}", env.mail_backend);
+    Ok(())
+}
+
+/// ส่งแบบ async ไม่บล็อก request (retry แบบ exponential backoff)
+pub fn queue(msg: EmailMessage) {
+    let Some(mailer) = MAILER.get().cloned() else {
+        tracing::error!("Mailer not initialized, dropping email to {}", msg.to);
+        return;
+    };
+
+    tokio::spawn(async move {
+        for attempt in 1..=MAX_ATTEMPTS {
+            match mailer.send(&msg).await {
+                Ok(()) => return,
+                Err(MailError::Address(e)) => {
+                    tracing::error!("Email to {} not sent: invalid address ({})", msg.to, e);
+                    return;
+                }
+                Err(e) if attempt < MAX_ATTEMPTS => {
+                    let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+                    tracing::warn!("Email to {} failed (attempt {}): {}, retrying in {:?}", msg.to, attempt, e, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => tracing::error!("Email to {} failed after {} attempts: {}", msg.to, attempt, e),
+            }
+        }
+    });
+}
@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use super::{EmailMessage, MailError, Mailer};
+
+/// อีเมลที่ถูกเก็บลง outbox (1 ไฟล์ JSON ต่อ 1 ฉบับ)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: String,
+    pub sent_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub message: EmailMessage,
+}
+
+/// backend สำหรับ dev: ไม่ส่งจริง เขียนลงโฟลเดอร์ให้ QA เปิดดูผ่าน /api/dev/outbox
+pub struct OutboxMailer {
+    dir: PathBuf,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: &str) -> Self {
+        Self { dir: PathBuf::from(dir) }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, msg: &EmailMessage) -> Result<(), MailError> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        let sent_at = Utc::now();
+        let id = format!("{}-{}", sent_at.format("%Y%m%d%H%M%S%6f"), rand::random::<u32>());
+        let entry = OutboxEntry { id: id.clone(), sent_at, message: msg.clone() };
+        let json = serde_json::to_vec_pretty(&entry).map_err(|e| MailError::Transport(e.to_string()))?;
+
+        fs::write(self.dir.join(format!("{}.json", id)), json)
+            .await
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        tracing::info!("📭 [OUTBOX] To: {}, Subject: {}", msg.to, msg.subject);
+        Ok(())
+    }
+}
+
+/// อ่านอีเมลใน outbox (ใหม่สุดก่อน) กรองตามผู้รับได้
+pub async fn list(dir: &str, to: Option<&str>, limit: usize) -> std::io::Result<Vec<OutboxEntry>> {
+    let dir = Path::new(dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(e) = entries.next_entry().await? {
+        let path = e.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            files.push(path);
+        }
+    }
+    // ชื่อไฟล์ขึ้นต้นด้วยเวลา -> sort ชื่อ = sort เวลา
+    files.sort();
+    files.reverse();
+
+    let mut out = Vec::new();
+    for path in files {
+        let Ok(bytes) = fs::read(&path).await else { continue };
+        let Ok(entry) = serde_json::from_slice::<OutboxEntry>(&bytes) else { continue };
+        if to.is_some_and(|t| !entry.message.to.eq_ignore_ascii_case(t)) {
+            continue;
+        }
+        out.push(entry);
+        if out.len() >= limit {
+            break;
+        }
+    }
+    Ok(out)
+}
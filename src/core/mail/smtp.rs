@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::config::env::Env;
+
+use super::{EmailMessage, MailError, Mailer};
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(env: &Env) -> Result<Self, MailError> {
+        if env.smtp_host.is_empty() {
+            return Err(MailError::Transport("SMTP_HOST must be set when MAIL_BACKEND=smtp".into()));
+        }
+
+        let from: Mailbox = env
+            .mail_from
+            .parse()
+            .map_err(|_| MailError::Address(env.mail_from.clone()))?;
+
+        // tls = implicit TLS (465), starttls = 587, none = plain (เช่น mailhog ใน docker)
+        let builder = match env.smtp_secure.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&env.smtp_host),
+            "none" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&env.smtp_host)),
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&env.smtp_host),
+        }
+        .map_err(|e| MailError::Transport(e.to_string()))?
+        .port(env.smtp_port);
+
+        let builder = if env.smtp_username.is_empty() {
+            builder
+        } else {
+            builder.credentials(Credentials::new(env.smtp_username.clone(), env.smtp_password.clone()))
+        };
+
+        Ok(Self { from, transport: builder.build() })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, msg: &EmailMessage) -> Result<(), MailError> {
+        let to: Mailbox = msg.to.parse().map_err(|_| MailError::Address(msg.to.clone()))?;
+        let builder = Message::builder().from(self.from.clone()).to(to).subject(&msg.subject);
+
+        let email = match &msg.html {
+            Some(html) => builder.multipart(MultiPart::alternative_plain_html(msg.text.clone(), html.clone())),
+            None => builder.header(ContentType::TEXT_PLAIN).body(msg.text.clone()),
+        }
+        .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+        Ok(())
+    }
+}
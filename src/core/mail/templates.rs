@@ -0,0 +1,228 @@
+use super::EmailMessage;
+
+/// ภาษาที่มี template (ไม่รู้จัก = อังกฤษ)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    Th,
+}
+
+impl Locale {
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Self::En),
+            "th" => Some(Self::Th),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Th => "th",
+        }
+    }
+
+    /// เลือกภาษาแรกที่รองรับจาก Accept-Language (เรียงตาม q)
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut langs: Vec<(f32, &str)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut it = part.split(';');
+                let tag = it.next()?.trim();
+                let q = it
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((q, tag))
+            })
+            .collect();
+        langs.sort_by(|a, b| b.0.total_cmp(&a.0));
+        langs.into_iter().find_map(|(_, tag)| Self::parse(tag))
+    }
+
+    /// ภาษาที่ user ตั้งไว้มาก่อน แล้วค่อยดู Accept-Language
+    pub fn resolve(user_locale: Option<&str>, accept_language: Option<&str>) -> Self {
+        user_locale
+            .and_then(Self::parse)
+            .or_else(|| accept_language.and_then(Self::from_accept_language))
+            .unwrap_or(Self::En)
+    }
+}
+
+pub enum Template<'a> {
+    VerificationCode { code: &'a str, minutes: i64 },
+    PasswordReset { link: &'a str, minutes: i64 },
+    PasswordChanged,
+    MagicLink { link: &'a str, minutes: i64 },
+    EmailChangeCode { code: &'a str, minutes: i64 },
+    EmailChanged { new_email: &'a str },
+    AccountDeletionScheduled { date: &'a str },
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn layout(app_name: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<body style="margin:0;padding:24px;background:#f5f5f5;font-family:Arial,Helvetica,sans-serif;color:#222">
+  <div style="max-width:480px;margin:0 auto;background:#fff;border-radius:8px;padding:24px">
+    <h2 style="margin-top:0">{app}</h2>
+    {body}
+  </div>
+</body>
+</html>"#,
+        app = escape(app_name),
+        body = body
+    )
+}
+
+impl Template<'_> {
+    /// คืน (subject, text, html body) ตามภาษา
+    fn parts(&self, locale: Locale, app_name: &str) -> (String, String, String) {
+        match (self, locale) {
+            (Self::VerificationCode { code, minutes }, Locale::En) => (
+                format!("{} verification code", app_name),
+                format!("Your verification code is {}.\nIt expires in {} minutes.", code, minutes),
+                format!(
+                    r#"<p>Your verification code is</p><p style="font-size:28px;font-weight:bold;letter-spacing:4px">{}</p><p>It expires in {} minutes.</p>"#,
+                    escape(code), minutes
+                ),
+            ),
+            (Self::VerificationCode { code, minutes }, Locale::Th) => (
+                format!("รหัสยืนยันอีเมล {}", app_name),
+                format!("รหัสยืนยันของคุณคือ {}\nรหัสนี้จะหมดอายุใน {} นาที", code, minutes),
+                format!(
+                    r#"<p>รหัสยืนยันของคุณคือ</p><p style="font-size:28px;font-weight:bold;letter-spacing:4px">{}</p><p>รหัสนี้จะหมดอายุใน {} นาที</p>"#,
+                    escape(code), minutes
+                ),
+            ),
+            (Self::PasswordReset { link, minutes }, Locale::En) => (
+                format!("{} password reset", app_name),
+                format!(
+                    "We received a request to reset your password.\nOpen this link within {} minutes:\n{}\n\nIf you did not request this, you can ignore this email.",
+                    minutes, link
+                ),
+                format!(
+                    r#"<p>We received a request to reset your password.</p><p><a href="{0}">Reset password</a></p><p>This link expires in {1} minutes. If you did not request this, you can ignore this email.</p>"#,
+                    escape(link), minutes
+                ),
+            ),
+            (Self::PasswordReset { link, minutes }, Locale::Th) => (
+                format!("ตั้งรหัสผ่านใหม่ {}", app_name),
+                format!(
+                    "เราได้รับคำขอตั้งรหัสผ่านใหม่ของคุณ\nกรุณาเปิดลิงก์นี้ภายใน {} นาที:\n{}\n\nหากคุณไม่ได้เป็นผู้ขอ สามารถเพิกเฉยอีเมลนี้ได้",
+                    minutes, link
+                ),
+                format!(
+                    r#"<p>เราได้รับคำขอตั้งรหัสผ่านใหม่ของคุณ</p><p><a href="{0}">ตั้งรหัสผ่านใหม่</a></p><p>ลิงก์นี้จะหมดอายุใน {1} นาที หากคุณไม่ได้เป็นผู้ขอ สามารถเพิกเฉยอีเมลนี้ได้</p>"#,
+                    escape(link), minutes
+                ),
+            ),
+            (Self::PasswordChanged, Locale::En) => (
+                format!("Your {} password was changed", app_name),
+                "Your password was just changed.\nIf this wasn't you, reset your password immediately and contact support.".to_string(),
+                "<p>Your password was just changed.</p><p>If this wasn't you, reset your password immediately and contact support.</p>".to_string(),
+            ),
+            (Self::PasswordChanged, Locale::Th) => (
+                format!("รหัสผ่าน {} ของคุณถูกเปลี่ยนแล้ว", app_name),
+                "รหัสผ่านของคุณเพิ่งถูกเปลี่ยน\nหากไม่ใช่คุณ กรุณาตั้งรหัสผ่านใหม่ทันทีและติดต่อฝ่ายสนับสนุน".to_string(),
+                "<p>รหัสผ่านของคุณเพิ่งถูกเปลี่ยน</p><p>หากไม่ใช่คุณ กรุณาตั้งรหัสผ่านใหม่ทันทีและติดต่อฝ่ายสนับสนุน</p>".to_string(),
+            ),
+            (Self::MagicLink { link, minutes }, Locale::En) => (
+                format!("Sign in to {}", app_name),
+                format!(
+                    "Open this link to sign in:\n{}\n\nThe link works once and expires in {} minutes. If you did not request this, you can ignore this email.",
+                    link, minutes
+                ),
+                format!(
+                    r#"<p>Click the button below to sign in.</p><p><a href="{0}">Sign in</a></p><p>The link works once and expires in {1} minutes. If you did not request this, you can ignore this email.</p>"#,
+                    escape(link), minutes
+                ),
+            ),
+            (Self::MagicLink { link, minutes }, Locale::Th) => (
+                format!("ลิงก์เข้าสู่ระบบ {}", app_name),
+                format!(
+                    "เปิดลิงก์นี้เพื่อเข้าสู่ระบบ:\n{}\n\nลิงก์ใช้ได้ครั้งเดียวและจะหมดอายุใน {} นาที หากคุณไม่ได้เป็นผู้ขอ สามารถเพิกเฉยอีเมลนี้ได้",
+                    link, minutes
+                ),
+                format!(
+                    r#"<p>กดปุ่มด้านล่างเพื่อเข้าสู่ระบบ</p><p><a href="{0}">เข้าสู่ระบบ</a></p><p>ลิงก์ใช้ได้ครั้งเดียวและจะหมดอายุใน {1} นาที หากคุณไม่ได้เป็นผู้ขอ สามารถเพิกเฉยอีเมลนี้ได้</p>"#,
+                    escape(link), minutes
+                ),
+            ),
+            (Self::EmailChangeCode { code, minutes }, Locale::En) => (
+                format!("Confirm your new {} email", app_name),
+                format!("Enter this code to confirm your new email address: {}\nIt expires in {} minutes.", code, minutes),
+                format!(
+                    r#"<p>Enter this code to confirm your new email address</p><p style="font-size:28px;font-weight:bold;letter-spacing:4px">{}</p><p>It expires in {} minutes.</p>"#,
+                    escape(code), minutes
+                ),
+            ),
+            (Self::EmailChangeCode { code, minutes }, Locale::Th) => (
+                format!("ยืนยันอีเมลใหม่ {}", app_name),
+                format!("กรอกรหัสนี้เพื่อยืนยันอีเมลใหม่ของคุณ: {}\nรหัสนี้จะหมดอายุใน {} นาที", code, minutes),
+                format!(
+                    r#"<p>กรอกรหัสนี้เพื่อยืนยันอีเมลใหม่ของคุณ</p><p style="font-size:28px;font-weight:bold;letter-spacing:4px">{}</p><p>รหัสนี้จะหมดอายุใน {} นาที</p>"#,
+                    escape(code), minutes
+                ),
+            ),
+            (Self::EmailChanged { new_email }, Locale::En) => (
+                format!("Your {} email was changed", app_name),
+                format!(
+                    "The email address on your account was changed to {}.\nIf this wasn't you, contact support immediately.",
+                    new_email
+                ),
+                format!(
+                    "<p>The email address on your account was changed to <b>{}</b>.</p><p>If this wasn't you, contact support immediately.</p>",
+                    escape(new_email)
+                ),
+            ),
+            (Self::EmailChanged { new_email }, Locale::Th) => (
+                format!("อีเมลบัญชี {} ของคุณถูกเปลี่ยนแล้ว", app_name),
+                format!("อีเมลของบัญชีคุณถูกเปลี่ยนเป็น {}\nหากไม่ใช่คุณ กรุณาติดต่อฝ่ายสนับสนุนทันที", new_email),
+                format!(
+                    "<p>อีเมลของบัญชีคุณถูกเปลี่ยนเป็น <b>{}</b></p><p>หากไม่ใช่คุณ กรุณาติดต่อฝ่ายสนับสนุนทันที</p>",
+                    escape(new_email)
+                ),
+            ),
+            (Self::AccountDeletionScheduled { date }, Locale::En) => (
+                format!("Your {} account will be deleted", app_name),
+                format!(
+                    "Your account and its data will be permanently deleted on {}.\nChanged your mind? Just sign in before then to cancel.",
+                    date
+                ),
+                format!(
+                    "<p>Your account and its data will be permanently deleted on <b>{}</b>.</p><p>Changed your mind? Just sign in before then to cancel.</p>",
+                    escape(date)
+                ),
+            ),
+            (Self::AccountDeletionScheduled { date }, Locale::Th) => (
+                format!("บัญชี {} ของคุณกำลังจะถูกลบ", app_name),
+                format!("บัญชีและข้อมูลของคุณจะถูกลบถาวรในวันที่ {}\nหากเปลี่ยนใจ เพียงเข้าสู่ระบบก่อนวันนั้นเพื่อยกเลิก", date),
+                format!(
+                    "<p>บัญชีและข้อมูลของคุณจะถูกลบถาวรในวันที่ <b>{}</b></p><p>หากเปลี่ยนใจ เพียงเข้าสู่ระบบก่อนวันนั้นเพื่อยกเลิก</p>",
+                    escape(date)
+                ),
+            ),
+        }
+    }
+
+    pub fn render(&self, to: &str, locale: Locale, app_name: &str) -> EmailMessage {
+        let (subject, text, body) = self.parts(locale, app_name);
+        EmailMessage {
+            to: to.to_string(),
+            subject,
+            text,
+            html: Some(layout(app_name, &body)),
+        }
+    }
+}
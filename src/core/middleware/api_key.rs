@@ -16,6 +16,15 @@ pub struct ApiClient {
     pub name: String,
     pub api_key: String,
     pub is_active: bool,
+    // name ของ client อื่นที่ยอม access token ของเขา (ปกติว่าง)
+    pub accepts_audiences: Vec<String>,
+}
+
+impl ApiClient {
+    /// access token ที่ออกให้ client `aud` ใช้กับ client นี้ได้ไหม
+    pub fn accepts(&self, aud: &str) -> bool {
+        aud == self.name || self.accepts_audiences.iter().any(|a| a == aud)
+    }
 }
 
 /// Middleware: require x-api-key (เหมือน pure-api1: app.use("/api", apiKeyAuth))
@@ -37,7 +46,7 @@ pub async fn mw_api_key_auth(
 
     let row = sqlx::query(
         r#"
-        SELECT id, name, api_key, is_active
+        SELECT id, name, api_key, is_active, accepts_audiences
         FROM api_clients
         WHERE api_key = $1
         LIMIT 1
@@ -61,9 +70,43 @@ pub async fn mw_api_key_auth(
         name: row.get("name"),
         api_key: row.get("api_key"),
         is_active,
+        accepts_audiences: row.get("accepts_audiences"),
     };
 
     req.extensions_mut().insert(client);
 
     Ok(next.run(req).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(name: &str, accepts: &[&str]) -> ApiClient {
+        ApiClient {
+            id: 1,
+            name: name.into(),
+            api_key: "k".into(),
+            is_active: true,
+            accepts_audiences: accepts.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn accepts_own_audience_only_by_default() {
+        let web = client("web", &[]);
+        assert!(web.accepts("web"));
+        assert!(!web.accepts("android"));
+        assert!(!web.accepts(""));
+    }
+
+    #[test]
+    fn accepts_listed_audiences() {
+        let admin = client("admin", &["web"]);
+        assert!(admin.accepts("admin"));
+        assert!(admin.accepts("web"));
+        assert!(!admin.accepts("android"));
+        // ไม่กลับทิศ: web ไม่ได้รับ token ของ admin
+        assert!(!client("web", &[]).accepts("admin"));
+    }
+}
@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{header, request::Parts};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::config::env::ENV;
+
+/// เครื่องที่ยิง request มา (เก็บลง session ตอน login / refresh)
+#[derive(Clone, Debug, Default)]
+pub struct Device {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+/// ip อยู่ในรายการ TRUSTED_PROXIES ไหม (รับทั้ง IP เดี่ยวและ CIDR เช่น 10.0.0.0/8)
+fn is_trusted(ip: IpAddr, proxies: &[String]) -> bool {
+    proxies.iter().any(|p| {
+        let (addr, bits) = match p.split_once('/') {
+            Some((a, b)) => (a, b.parse::<u32>().ok()),
+            None => (p.as_str(), None),
+        };
+        match (addr.parse::<IpAddr>(), ip) {
+            (Ok(IpAddr::V4(net)), IpAddr::V4(ip)) => {
+                let bits = bits.unwrap_or(32).min(32);
+                let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (Ok(IpAddr::V6(net)), IpAddr::V6(ip)) => {
+                let bits = bits.unwrap_or(128).min(128);
+                let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    })
+}
+
+/// IP จริงของ client: เชื่อ X-Forwarded-For เฉพาะตอนต่อมาจาก proxy ที่ตั้งไว้
+/// ไล่จากขวา (hop ที่ใกล้เราที่สุด) ข้าม proxy ของเราเอง ตัวแรกที่ไม่ใช่ proxy = client
+/// (ตัวซ้ายสุด client ใส่มาเองได้ เชื่อไม่ได้)
+fn client_ip(peer: Option<IpAddr>, forwarded: Option<&str>, proxies: &[String]) -> Option<IpAddr> {
+    let peer = peer?;
+    if !is_trusted(peer, proxies) {
+        return Some(peer);
+    }
+    let mut ip = peer;
+    for hop in forwarded.unwrap_or_default().rsplit(',') {
+        let Ok(hop) = hop.trim().parse::<IpAddr>() else { break };
+        ip = hop;
+        if !is_trusted(hop, proxies) {
+            break;
+        }
+    }
+    Some(ip)
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Device {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.chars().take(512).collect::<String>())
+            .filter(|s| !s.is_empty());
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let forwarded = parts.headers.get("x-forwarded-for").and_then(|v| v.to_str().ok());
+        let proxies = ENV.get().map(|e| e.trusted_proxies.as_slice()).unwrap_or_default();
+        let ip = client_ip(peer, forwarded, proxies).map(|ip| ip.to_string());
+
+        Ok(Device { user_agent, ip })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn proxies(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn trusted_matches_ip_and_cidr() {
+        let list = proxies(&["10.0.0.0/8", "192.168.1.5", "fd00::/8"]);
+        assert!(is_trusted(ip("10.1.2.3"), &list));
+        assert!(is_trusted(ip("192.168.1.5"), &list));
+        assert!(!is_trusted(ip("192.168.1.6"), &list));
+        assert!(is_trusted(ip("fd12::1"), &list));
+        assert!(!is_trusted(ip("fe80::1"), &list));
+        assert!(is_trusted(ip("8.8.8.8"), &proxies(&["0.0.0.0/0"])));
+        assert!(!is_trusted(ip("10.0.0.1"), &[]));
+    }
+
+    #[test]
+    fn forwarded_ignored_without_trusted_peer() {
+        let peer = Some(ip("203.0.113.9"));
+        assert_eq!(client_ip(peer, Some("1.2.3.4"), &[]), peer);
+        assert_eq!(client_ip(peer, Some("1.2.3.4"), &proxies(&["10.0.0.0/8"])), peer);
+        assert_eq!(client_ip(None, Some("1.2.3.4"), &[]), None);
+    }
+
+    #[test]
+    fn forwarded_walks_from_the_right() {
+        let list = proxies(&["10.0.0.0/8"]);
+        let peer = Some(ip("10.0.0.2"));
+        assert_eq!(client_ip(peer, Some("198.51.100.7"), &list), Some(ip("198.51.100.7")));
+        // ตัวซ้ายสุดที่ client ปลอมมาไม่ถูกใช้
+        assert_eq!(client_ip(peer, Some("6.6.6.6, 198.51.100.7, 10.0.0.3"), &list), Some(ip("198.51.100.7")));
+        // ค่าพัง -> ใช้ hop ล่าสุดที่อ่านได้
+        assert_eq!(client_ip(peer, Some("garbage"), &list), peer);
+        assert_eq!(client_ip(peer, None, &list), peer);
+    }
+}
@@ -1,7 +1,12 @@
-use axum::{extract::Request, middleware::Next, response::Response};
+use axum::{extract::{OriginalUri, Request}, http::Method, middleware::Next, response::Response, Extension};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use crate::config::db::DB;
 use crate::core::errors::AppError;
-use crate::core::utils::jwt;
+use crate::core::middleware::api_key::ApiClient;
+use crate::core::utils::{jwt, pat, rbac, revocation};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AuthUser {
@@ -9,38 +14,184 @@ pub struct AuthUser {
     pub email: String,
     // pub name: String, // ❌ ลบออก
     pub role: String,
+    // เก็บไว้ใช้ตอน revoke token ปัจจุบัน (เช่น logout)
+    pub jti: String,
+    pub exp: usize,
+    // เวลาที่ login จริง (ดู api::auth::reauth)
+    pub auth_time: usize,
+    // session ที่ token นี้มาจาก (ดู api::sessions) None = personal access token
+    pub sid: Option<i32>,
+    // id ของ admin ที่สวมรอยอยู่ (None = user ใช้เอง) ดู api::impersonation
+    pub act: Option<i32>,
+    // เรียกด้วย personal access token (id ของ token) ดู api::tokens
+    pub pat_id: Option<i32>,
 }
 
-pub async fn mw_jwt_auth(mut req: Request, next: Next) -> Result<Response, AppError> {
-    let token = req.headers().get("authorization")
+/// ดึง Bearer token ออกจาก header (ไม่มี/ว่าง = None)
+pub fn bearer_token(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers.get("authorization")
         .and_then(|h| h.to_str().ok())
         .and_then(|s| s.strip_prefix("Bearer "))
-        .map(|s| s.trim().to_string());
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
 
-    let token = match token {
-        Some(t) if !t.is_empty() => t,
-        _ => return Err(AppError::unauthorized("JWT_MISSING", "Missing Authorization Bearer token")),
-    };
+/// Bearer pat_... -> AuthUser ของเจ้าของ token (scope read ใช้ได้แค่ GET/HEAD)
+async fn pat_user(db: &DB, token: &str, method: &Method) -> Result<AuthUser, AppError> {
+    let p = pat::resolve(db, token)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("PAT_INVALID", "Invalid or expired access token"))?;
+
+    if !pat::allows(&p.scopes, method) {
+        return Err(AppError::forbidden("PAT_SCOPE", "Access token does not have the write scope"));
+    }
+
+    Ok(AuthUser {
+        id: p.user_id,
+        email: p.email,
+        role: p.role,
+        jti: format!("pat_{}", p.id),
+        exp: p.exp,
+        // ไม่นับเป็นการ login (งานที่ต้อง re-auth ต้องใส่รหัสผ่าน)
+        auth_time: 0,
+        sid: None,
+        act: None,
+        pat_id: Some(p.id),
+    })
+}
+
+pub async fn mw_jwt_auth(Extension(db): Extension<DB>, mut req: Request, next: Next) -> Result<Response, AppError> {
+    let token = bearer_token(req.headers())
+        .ok_or_else(|| AppError::unauthorized("JWT_MISSING", "Missing Authorization Bearer token"))?;
+
+    if pat::is_pat(&token) {
+        let user = pat_user(&db, &token, req.method()).await?;
+        req.extensions_mut().insert(user);
+        return Ok(next.run(req).await);
+    }
 
     let claims = jwt::verify(&token).map_err(|_| AppError::unauthorized("JWT_INVALID", "Invalid token"))?;
 
+    // token ที่ออกให้ client อื่น (เช่นขโมยจากแอป Android มายิงผ่าน key ของเว็บ) ใช้ไม่ได้
+    let client = req.extensions().get::<ApiClient>()
+        .ok_or_else(|| AppError::unauthorized("API_KEY_MISSING", "Missing x-api-key"))?;
+    if !client.accepts(&claims.aud) {
+        return Err(AppError::unauthorized("JWT_AUDIENCE_MISMATCH", "Token was issued for a different client"));
+    }
+
+    if revocation::is_revoked(&db, &claims).await? {
+        return Err(AppError::unauthorized("JWT_REVOKED", "Token has been revoked"));
+    }
+    if revocation::is_session_revoked(&db, claims.sid).await? {
+        return Err(AppError::unauthorized("SESSION_REVOKED", "Session has been revoked"));
+    }
+
     let user = AuthUser {
         id: claims.sub,
         email: claims.email,
         // name: claims.name, // ❌ ลบออก
         role: claims.role,
+        jti: claims.jti,
+        exp: claims.exp,
+        auth_time: claims.auth_time,
+        sid: Some(claims.sid),
+        act: claims.act.map(|a| a.sub),
+        pat_id: None,
     };
 
+    if let Some(admin_id) = user.act {
+        // router ที่ nest ไว้ตัด prefix ออกจาก uri ไปแล้ว ใช้ OriginalUri แทน
+        let path = req.extensions().get::<OriginalUri>().map(|u| u.path().to_string()).unwrap_or_else(|| req.uri().path().to_string());
+        tracing::info!("🎭 admin {} as user {}: {} {}", admin_id, user.id, req.method(), path);
+    }
+
     req.extensions_mut().insert(user);
     Ok(next.run(req).await)
 }
 
-pub async fn mw_require_admin(req: Request, next: Next) -> Result<Response, AppError> {
-    let user = req.extensions().get::<AuthUser>().cloned()
+/// งานที่ต้องเป็นเจ้าของบัญชีที่ login เองเท่านั้น (รหัสผ่าน, อีเมล, ลบบัญชี, 2FA, passkey, สร้าง PAT ...)
+/// ห้ามทำตอน admin สวมรอย หรือผ่าน personal access token
+/// ใส่เป็น route_layer ก่อน mw_jwt_auth (ให้รันหลัง)
+pub async fn mw_deny_delegated(req: Request, next: Next) -> Result<Response, AppError> {
+    let user = req.extensions().get::<AuthUser>()
         .ok_or_else(|| AppError::unauthorized("JWT_MISSING", "Missing auth user"))?;
 
-    if user.role != "admin" {
-        return Err(AppError::forbidden("FORBIDDEN", "Admin only"));
+    if user.act.is_some() {
+        return Err(AppError::forbidden("IMPERSONATION_FORBIDDEN", "Not allowed while impersonating a user"));
+    }
+    if user.pat_id.is_some() {
+        return Err(AppError::forbidden("PAT_FORBIDDEN", "Not allowed with a personal access token"));
     }
     Ok(next.run(req).await)
-}
\ No newline at end of file
+}
+
+type MwFuture = Pin<Box<dyn Future<Output = Result<Response, AppError>> + Send>>;
+
+/// ต้องมี permission นี้ (ผ่าน role ของ user) เช่น
+/// `.route_layer(middleware::from_fn(jwt_auth::mw_require_permission("carousel:write")))`
+/// token สวมรอยใช้ไม่ได้ (งานของ staff ต้องทำในชื่อตัวเอง) ส่วน PAT ใช้ได้ถ้ามี scope admin (ดู pat::resolve)
+/// ใส่เป็น route_layer ก่อน mw_jwt_auth (ให้รันหลัง)
+pub fn mw_require_permission(permission: &'static str) -> impl Fn(Request, Next) -> MwFuture + Clone + Send + Sync + 'static {
+    move |req, next| Box::pin(require_permission(permission, req, next))
+}
+
+async fn require_permission(permission: &'static str, req: Request, next: Next) -> Result<Response, AppError> {
+    let user = req.extensions().get::<AuthUser>()
+        .ok_or_else(|| AppError::unauthorized("JWT_MISSING", "Missing auth user"))?;
+    let db = req.extensions().get::<DB>()
+        .ok_or_else(|| AppError::internal("Missing DB extension"))?;
+
+    if user.act.is_some() {
+        return Err(AppError::forbidden("IMPERSONATION_FORBIDDEN", "Not allowed while impersonating a user"));
+    }
+
+    if !rbac::has_permission(db, &user.role, permission).await? {
+        return Err(AppError::forbidden("FORBIDDEN", "You do not have permission to do this")
+            .with_details(json!({ "permission": permission })));
+    }
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn user(act: Option<i32>) -> AuthUser {
+        AuthUser {
+            id: 3,
+            email: "g@x.com".into(),
+            role: "admin".into(),
+            jti: "j".into(),
+            exp: 0,
+            auth_time: 0,
+            sid: Some(1),
+            act,
+            pat_id: None,
+        }
+    }
+
+    // pool แบบ lazy ไม่ต่อ DB จริง: เคสที่ต้องถูกปฏิเสธก่อนถึง query
+    async fn call(user: Option<AuthUser>) -> StatusCode {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn(mw_require_permission("users:manage")));
+        let mut req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        req.extensions_mut().insert(DB { pool: sqlx::PgPool::connect_lazy("postgres://localhost/none").unwrap() });
+        if let Some(user) = user {
+            req.extensions_mut().insert(user);
+        }
+        app.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn require_permission_denies_impersonation() {
+        assert_eq!(call(Some(user(Some(1)))).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn require_permission_needs_auth_user() {
+        assert_eq!(call(None).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod api_key;
+pub mod device;
 pub mod jwt_auth;
 // rateLimit เราจะเรียกใช้จาก library โดยตรงใน router
\ No newline at end of file
@@ -1,3 +1,5 @@
 pub mod errors;
+pub mod jobs;
+pub mod mail;
 pub mod middleware;
 pub mod utils;
\ No newline at end of file
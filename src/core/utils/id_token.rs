@@ -0,0 +1,112 @@
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::core::errors::AppError;
+
+// เก็บ JWKS ไว้ 1 ชม. ถ้าเจอ kid ที่ไม่รู้จักจะโหลดใหม่ได้ไม่เกินนาทีละครั้ง (กันโดนยิงให้ fetch รัวๆ)
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+struct CachedJwks {
+    fetched_at: Instant,
+    keys: JwkSet,
+}
+
+static JWKS_CACHE: OnceLock<Mutex<HashMap<String, CachedJwks>>> = OnceLock::new();
+
+fn invalid(msg: &str) -> AppError {
+    AppError::unauthorized("INVALID_ID_TOKEN", msg)
+}
+
+/// source = URL (https://...) หรือไฟล์ local (file:/path/jwks.json) ไว้เทสแบบ offline
+async fn fetch_jwks(source: &str) -> Result<JwkSet, AppError> {
+    let raw = if let Some(path) = source.strip_prefix("file:") {
+        tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| AppError::internal(format!("Read JWKS file failed: {}", e)))?
+    } else {
+        reqwest::get(source)
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| AppError::internal(format!("Fetch JWKS failed: {}", e)))?
+            .text()
+            .await
+            .map_err(|e| AppError::internal(format!("Fetch JWKS failed: {}", e)))?
+    };
+    serde_json::from_str(&raw).map_err(|e| AppError::internal(format!("Invalid JWKS: {}", e)))
+}
+
+async fn jwks(source: &str, kid: &str) -> Result<JwkSet, AppError> {
+    let cache = JWKS_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    let cached = {
+        let map = cache.lock().unwrap();
+        map.get(source).map(|c| (c.fetched_at, c.keys.clone()))
+    };
+    if let Some((fetched_at, keys)) = cached {
+        let fresh = fetched_at.elapsed() < JWKS_TTL;
+        let has_kid = keys.find(kid).is_some();
+        if (fresh && has_kid) || fetched_at.elapsed() < JWKS_MIN_REFRESH {
+            return Ok(keys);
+        }
+    }
+
+    let keys = fetch_jwks(source).await?;
+    cache.lock().unwrap().insert(
+        source.to_string(),
+        CachedJwks { fetched_at: Instant::now(), keys: keys.clone() },
+    );
+    Ok(keys)
+}
+
+/// ตรวจ signature + aud/iss/exp แล้วคืน claims ที่เชื่อถือได้
+///
+/// RS*/PS*/ES*/EdDSA ตรวจกับ JWKS ส่วน HS* (เช่น LINE) ใช้ client secret เป็น key ตาม OIDC Core 10.1
+pub async fn verify<T: DeserializeOwned>(
+    token: &str,
+    jwks_uri: Option<&str>,
+    client_secret: &str,
+    issuers: &[String],
+    audience: &str,
+) -> Result<T, AppError> {
+    if issuers.is_empty() {
+        return Err(AppError::internal("ID token issuer is not configured"));
+    }
+    let header = decode_header(token).map_err(|_| invalid("Malformed ID token"))?;
+
+    let key = match header.alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            // ไม่มี secret = ใครก็เซ็นได้ ห้ามรับ
+            if client_secret.is_empty() {
+                return Err(invalid("Unsupported ID token algorithm"));
+            }
+            DecodingKey::from_secret(client_secret.as_bytes())
+        }
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 | Algorithm::PS256 | Algorithm::PS384
+        | Algorithm::PS512 | Algorithm::ES256 | Algorithm::ES384 | Algorithm::EdDSA => {
+            let jwks_uri = jwks_uri.ok_or_else(|| AppError::internal("JWKS URI is not configured"))?;
+            let kid = header.kid.ok_or_else(|| invalid("ID token has no kid"))?;
+            let keys = jwks(jwks_uri, &kid).await?;
+            let jwk = keys.find(&kid).ok_or_else(|| invalid("Unknown signing key"))?;
+            DecodingKey::from_jwk(jwk).map_err(|_| invalid("Unsupported signing key"))?
+        }
+    };
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[audience]);
+    validation.set_issuer(issuers);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    decode::<T>(token, &key, &validation)
+        .map(|d| d.claims)
+        .map_err(|e| match e.kind() {
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => invalid("ID token expired"),
+            jsonwebtoken::errors::ErrorKind::InvalidAudience => invalid("ID token audience mismatch"),
+            jsonwebtoken::errors::ErrorKind::InvalidIssuer => invalid("ID token issuer mismatch"),
+            _ => invalid("Invalid ID token"),
+        })
+}
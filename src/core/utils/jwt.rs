@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::config::env::{Env, ENV};
+use crate::core::utils::jwt_keys::{self, KeyStore};
+use crate::core::utils::token_hash;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -12,17 +14,47 @@ pub struct Claims {
     pub role: String,
     pub exp: usize,
     pub iat: usize,
+    pub iss: String,
+    // name ของ api client ที่ login (เช็คกับ x-api-key ใน mw_jwt_auth)
+    pub aud: String,
+    // ใช้อ้างอิงตอน revoke (ดู core::utils::revocation)
+    pub jti: String,
+    // เวลาที่ login จริง (คงเดิมตอน refresh) ใช้เช็ค re-auth
+    #[serde(default)]
+    pub auth_time: usize,
+    // sessions.id (revoke ราย device ได้)
+    pub sid: i32,
+    // มีค่า = admin ที่สวมรอยเป็น user นี้อยู่ (RFC 8693 "act")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
 }
 
-fn now_ts() -> usize {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Actor {
+    pub sub: i32,
+}
+
+/// token ชั่วคราวสำหรับงานเฉพาะ (เช่น "mfa_pending") ใช้แทน access token ไม่ได้
+/// เพราะไม่มี email/role -> decode เป็น Claims ไม่ผ่าน
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PurposeClaims {
+    pub sub: i32,
+    pub purpose: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub jti: String,
+}
+
+pub fn now_ts() -> usize {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs() as usize
 }
 
-fn exp_from_env(env: &Env) -> usize {
-    let s = env.jwt_expires_in.trim();
+/// แปลงค่าแบบ "15m", "30d", "3600" เป็นจำนวนวินาที (fallback 7 วัน)
+fn parse_duration(s: &str) -> usize {
+    let s = s.trim();
     if s.is_empty() { return 7 * 24 * 60 * 60; }
     let last = s.chars().last().unwrap_or(' ');
     if last.is_ascii_digit() { return s.parse::<usize>().unwrap_or(7 * 24 * 60 * 60); }
@@ -33,15 +65,28 @@ fn exp_from_env(env: &Env) -> usize {
     }
 }
 
+/// อายุ access token (วินาที) จาก JWT_EXPIRES_IN
+pub fn access_ttl(env: &Env) -> usize {
+    parse_duration(&env.jwt_expires_in)
+}
+
+/// อายุ refresh token (วินาที) จาก JWT_REFRESH_EXPIRES_IN
+pub fn refresh_ttl(env: &Env) -> usize {
+    parse_duration(&env.jwt_refresh_expires_in)
+}
+
 pub fn sign(
     user_id: i32,
     email: String,
     // name: String, // ❌ ลบออก
     role: String,
+    auth_time: usize,
+    aud: &str,
+    sid: i32,
     env: &Env,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let iat = now_ts();
-    let exp = iat + exp_from_env(env);
+    let exp = iat + access_ttl(env);
 
     let claims = Claims {
         sub: user_id,
@@ -50,25 +95,212 @@ pub fn sign(
         role,
         exp,
         iat,
+        iss: env.jwt_issuer.clone(),
+        aud: aud.to_string(),
+        jti: token_hash::create_random_token(),
+        auth_time,
+        sid,
+        act: None,
     };
 
+    encode_access(&claims, env)
+}
+
+/// เซ็น access token จาก claims ที่สร้างเอง (เช่น token สวมรอยที่มี act)
+pub fn encode_access(claims: &Claims, env: &Env) -> Result<String, jsonwebtoken::errors::Error> {
+    encode_with(claims, jwt_keys::keys(), &env.jwt_secret)
+}
+
+fn encode_with(claims: &Claims, keys: &KeyStore, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
+    // มี JWT_SIGNING_KEY_FILE = เซ็นด้วย private key พร้อม kid
+    if let Some(signing) = &keys.signing {
+        let mut header = Header::new(signing.alg);
+        header.kid = Some(signing.kid.clone());
+        return encode(&header, claims, &signing.key);
+    }
+
     encode(
         &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(env.jwt_secret.as_bytes()),
+        claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
     )
 }
 
+/// ตรวจลายเซ็น + exp + iss (aud ต้องเทียบกับ client ของ request เอง ดู mw_jwt_auth)
 pub fn verify(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let env = ENV.get().expect("ENV not initialized");
+    decode_access(token, jwt_keys::keys(), &env.jwt_secret, &env.jwt_issuer)
+}
+
+fn decode_access(token: &str, keys: &KeyStore, secret: &str, issuer: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let header = jsonwebtoken::decode_header(token)?;
+    let validation = |alg: Algorithm| {
+        let mut v = Validation::new(alg);
+        v.validate_exp = true;
+        v.validate_aud = false;
+        v.set_issuer(&[issuer]);
+        v
+    };
+
+    let data = match header.kid {
+        // เลือก key จาก kid (alg ต้องตรงกับ key ไม่เชื่อ alg ใน header)
+        Some(kid) => {
+            let (alg, key) = keys
+                .verifying
+                .get(&kid)
+                .ok_or(jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+            decode::<Claims>(token, key, &validation(*alg))?
+        }
+        // token HS256 แบบเดิม รับเฉพาะตอนยังไม่ได้เปิดใช้ key คู่
+        None => {
+            if keys.signing.is_some() {
+                return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+            }
+            decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(secret.as_bytes()),
+                &validation(Algorithm::HS256),
+            )?
+        }
+    };
+
+    Ok(data.claims)
+}
+
+/// คืน (token, jti) เก็บ jti ไว้ฝั่ง server ถ้าต้องการให้ใช้ได้ครั้งเดียว
+pub fn sign_purpose(
+    user_id: i32,
+    purpose: &str,
+    ttl_secs: usize,
+    env: &Env,
+) -> Result<(String, String), jsonwebtoken::errors::Error> {
+    let iat = now_ts();
+    let claims = PurposeClaims {
+        sub: user_id,
+        purpose: purpose.to_string(),
+        exp: iat + ttl_secs,
+        iat,
+        jti: token_hash::create_random_token(),
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(env.jwt_secret.as_bytes()),
+    )?;
+    Ok((token, claims.jti))
+}
+
+pub fn verify_purpose(token: &str, purpose: &str) -> Result<PurposeClaims, jsonwebtoken::errors::Error> {
     let env = ENV.get().expect("ENV not initialized");
     let mut validation = Validation::new(Algorithm::HS256);
     validation.validate_exp = true;
 
-    let data = decode::<Claims>(
+    let data = decode::<PurposeClaims>(
         token,
         &DecodingKey::from_secret(env.jwt_secret.as_bytes()),
         &validation,
     )?;
 
+    if data.claims.purpose != purpose {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
     Ok(data.claims)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey};
+    use std::collections::HashMap;
+
+    const SECRET: &str = "test-secret";
+    const ISS: &str = "pure-api";
+
+    fn claims(iss: &str) -> Claims {
+        let iat = now_ts();
+        Claims {
+            sub: 1,
+            email: "a@x.com".into(),
+            role: "user".into(),
+            exp: iat + 60,
+            iat,
+            iss: iss.into(),
+            aud: "web".into(),
+            jti: token_hash::create_random_token(),
+            auth_time: iat,
+            sid: 1,
+            act: None,
+        }
+    }
+
+    fn key_file(seed: u8) -> String {
+        let pem = ed25519_dalek::SigningKey::from_bytes(&[seed; 32]).to_pkcs8_pem(LineEnding::LF).unwrap();
+        let path = std::env::temp_dir().join(format!("jwt-{}-{}.pem", std::process::id(), seed));
+        std::fs::write(&path, pem.as_bytes()).unwrap();
+        path.to_string_lossy().into()
+    }
+
+    fn hs256_only() -> KeyStore {
+        KeyStore { signing: None, verifying: HashMap::new(), jwks: jsonwebtoken::jwk::JwkSet { keys: Vec::new() } }
+    }
+
+    #[test]
+    fn signs_with_kid_and_verifies() {
+        let keys = jwt_keys::build(&key_file(10), &[]).unwrap();
+        let token = encode_with(&claims(ISS), &keys, SECRET).unwrap();
+
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid.as_ref(), keys.signing.as_ref().map(|s| &s.kid));
+        assert_eq!(decode_access(&token, &keys, SECRET, ISS).unwrap().sub, 1);
+    }
+
+    #[test]
+    fn rotated_key_still_verifies_until_removed() {
+        let old = jwt_keys::build(&key_file(11), &[]).unwrap();
+        let token = encode_with(&claims(ISS), &old, SECRET).unwrap();
+
+        // เซ็นด้วย key ใหม่ แต่ยังเก็บ key เก่าไว้ตรวจ
+        let rotated = jwt_keys::build(&key_file(12), &[key_file(11)]).unwrap();
+        assert!(decode_access(&token, &rotated, SECRET, ISS).is_ok());
+
+        // เอา key เก่าออกแล้ว -> kid ไม่รู้จัก
+        let dropped = jwt_keys::build(&key_file(12), &[]).unwrap();
+        assert!(decode_access(&token, &dropped, SECRET, ISS).is_err());
+    }
+
+    #[test]
+    fn hs256_rejected_once_keys_are_enabled() {
+        let token = encode_with(&claims(ISS), &hs256_only(), SECRET).unwrap();
+        assert!(decode_access(&token, &hs256_only(), SECRET, ISS).is_ok());
+        assert!(decode_access(&token, &hs256_only(), "other-secret", ISS).is_err());
+
+        let keys = jwt_keys::build(&key_file(13), &[]).unwrap();
+        assert!(decode_access(&token, &keys, SECRET, ISS).is_err());
+    }
+
+    #[test]
+    fn alg_comes_from_key_not_header() {
+        let keys = jwt_keys::build(&key_file(14), &[]).unwrap();
+        // HS256 ที่แปะ kid ของ EdDSA key มา
+        let header = Header { kid: keys.signing.as_ref().map(|s| s.kid.clone()), ..Default::default() };
+        let token = encode(&header, &claims(ISS), &EncodingKey::from_secret(SECRET.as_bytes())).unwrap();
+        assert!(decode_access(&token, &keys, SECRET, ISS).is_err());
+    }
+
+    #[test]
+    fn rejects_other_issuer() {
+        let token = encode_with(&claims("someone-else"), &hs256_only(), SECRET).unwrap();
+        assert!(decode_access(&token, &hs256_only(), SECRET, ISS).is_err());
+    }
+
+    // aud เทียบที่ mw_jwt_auth กับ client ของ request ไม่ใช่ที่นี่
+    #[test]
+    fn keeps_aud_for_middleware_check() {
+        let mut c = claims(ISS);
+        c.aud = "android".into();
+        let token = encode_with(&c, &hs256_only(), SECRET).unwrap();
+        assert_eq!(decode_access(&token, &hs256_only(), SECRET, ISS).unwrap().aud, "android");
+    }
+}
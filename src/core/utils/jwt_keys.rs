@@ -0,0 +1,209 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::config::env::Env;
+
+/// key ที่ใช้เซ็น access token ตอนนี้
+pub struct SigningKey {
+    pub kid: String,
+    pub alg: Algorithm,
+    pub key: EncodingKey,
+}
+
+/// key ทั้งหมดที่โหลดจาก JWT_SIGNING_KEY_FILE / JWT_VERIFY_KEY_FILES
+pub struct KeyStore {
+    pub signing: Option<SigningKey>,
+    // kid -> key ที่ใช้ตรวจ (รวม key ที่เซ็นอยู่ และ key เก่าที่รอ token หมดอายุ)
+    pub verifying: HashMap<String, (Algorithm, DecodingKey)>,
+    pub jwks: JwkSet,
+}
+
+static KEYS: OnceLock<KeyStore> = OnceLock::new();
+
+pub fn keys() -> &'static KeyStore {
+    KEYS.get().expect("JWT keys not initialized")
+}
+
+/// kid = RFC 7638 thumbprint (sha256 ของ member ที่จำเป็น เรียงตามชื่อ)
+fn thumbprint(canonical: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// ส่วนที่ต้องใช้ทำ JWK จาก public key
+enum PublicKey {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    Ed25519([u8; 32]),
+}
+
+/// public key -> (alg, DecodingKey, JWK)
+fn public_parts(public: &PublicKey, path: &str) -> Result<(Algorithm, DecodingKey, Jwk), String> {
+    let (alg, key_algorithm, algorithm, kid) = match public {
+        PublicKey::Rsa { n, e } => {
+            let n = URL_SAFE_NO_PAD.encode(n);
+            let e = URL_SAFE_NO_PAD.encode(e);
+            let kid = thumbprint(&format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, e, n));
+            let params = AlgorithmParameters::RSA(RSAKeyParameters { key_type: RSAKeyType::RSA, n, e });
+            (Algorithm::RS256, KeyAlgorithm::RS256, params, kid)
+        }
+        PublicKey::Ed25519(raw) => {
+            let x = URL_SAFE_NO_PAD.encode(raw);
+            let kid = thumbprint(&format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, x));
+            let params = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x,
+            });
+            (Algorithm::EdDSA, KeyAlgorithm::EdDSA, params, kid)
+        }
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid),
+            ..Default::default()
+        },
+        algorithm,
+    };
+    let decoding = DecodingKey::from_jwk(&jwk).map_err(|e| format!("{}: {}", path, e))?;
+    Ok((alg, decoding, jwk))
+}
+
+fn rsa_public(key: &RsaPublicKey) -> PublicKey {
+    PublicKey::Rsa { n: key.n().to_bytes_be(), e: key.e().to_bytes_be() }
+}
+
+/// อ่าน PEM (private หรือ public key, PKCS#8 / PKCS#1 / SPKI) คืน public parts + EncodingKey ถ้าเป็น private key
+fn load_file(path: &str) -> Result<(Algorithm, DecodingKey, Jwk, Option<EncodingKey>), String> {
+    let pem = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let private = if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(&pem).or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem)) {
+        Some((rsa_public(&key.to_public_key()), EncodingKey::from_rsa_pem(pem.as_bytes())))
+    } else if let Ok(key) = ed25519_dalek::SigningKey::from_pkcs8_pem(&pem) {
+        Some((PublicKey::Ed25519(key.verifying_key().to_bytes()), EncodingKey::from_ed_pem(pem.as_bytes())))
+    } else {
+        None
+    };
+    if let Some((public, encoding)) = private {
+        let (alg, decoding, jwk) = public_parts(&public, path)?;
+        let encoding = encoding.map_err(|e| format!("{}: {}", path, e))?;
+        return Ok((alg, decoding, jwk, Some(encoding)));
+    }
+
+    let public = if let Ok(key) = RsaPublicKey::from_public_key_pem(&pem).or_else(|_| RsaPublicKey::from_pkcs1_pem(&pem)) {
+        rsa_public(&key)
+    } else if let Ok(key) = ed25519_dalek::VerifyingKey::from_public_key_pem(&pem) {
+        PublicKey::Ed25519(key.to_bytes())
+    } else {
+        return Err(format!("{}: not an RSA or Ed25519 PEM key", path));
+    };
+    let (alg, decoding, jwk) = public_parts(&public, path)?;
+    Ok((alg, decoding, jwk, None))
+}
+
+/// โหลด key ตอนเริ่ม server (ไม่ตั้ง JWT_SIGNING_KEY_FILE = ใช้ HS256 + JWT_SECRET ตามเดิม)
+pub fn init(env: &Env) -> Result<(), String> {
+    let store = build(&env.jwt_signing_key_file, &env.jwt_verify_key_files)?;
+    match &store.signing {
+        Some(s) => tracing::info!("🔑 JWT signing: {:?} kid={} ({} verify keys)", s.alg, s.kid, store.verifying.len()),
+        None => tracing::info!("🔑 JWT signing: HS256 (JWT_SECRET)"),
+    }
+    let _ = KEYS.set(store);
+    Ok(())
+}
+
+/// สร้าง KeyStore จากไฟล์ PEM (init เรียกตัวนี้ แล้วเก็บไว้ใน KEYS)
+pub fn build(signing_file: &str, verify_files: &[String]) -> Result<KeyStore, String> {
+    let mut store = KeyStore { signing: None, verifying: HashMap::new(), jwks: JwkSet { keys: Vec::new() } };
+
+    if !signing_file.is_empty() {
+        let (alg, decoding, jwk, encoding) = load_file(signing_file)?;
+        let key = encoding.ok_or_else(|| format!("{}: JWT_SIGNING_KEY_FILE must be a private key", signing_file))?;
+        let kid = jwk.common.key_id.clone().unwrap_or_default();
+        store.verifying.insert(kid.clone(), (alg, decoding));
+        store.jwks.keys.push(jwk);
+        store.signing = Some(SigningKey { kid, alg, key });
+    }
+
+    for path in verify_files {
+        let (alg, decoding, jwk, _) = load_file(path)?;
+        let kid = jwk.common.key_id.clone().unwrap_or_default();
+        if store.verifying.insert(kid, (alg, decoding)).is_none() {
+            store.jwks.keys.push(jwk);
+        }
+    }
+
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey, EncodePublicKey};
+
+    // RFC 8037 A.1
+    const SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+    fn write(name: &str, pem: &str) -> String {
+        let path = std::env::temp_dir().join(format!("jwt-keys-{}-{}.pem", std::process::id(), name));
+        std::fs::write(&path, pem).unwrap();
+        path.to_string_lossy().into()
+    }
+
+    fn ed25519(seed: [u8; 32], name: &str, public: bool) -> String {
+        let key = ed25519_dalek::SigningKey::from_bytes(&seed);
+        let pem = if public {
+            key.verifying_key().to_public_key_pem(LineEnding::LF).unwrap()
+        } else {
+            key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string()
+        };
+        write(name, &pem)
+    }
+
+    #[test]
+    fn kid_is_rfc7638_thumbprint() {
+        let seed: [u8; 32] = hex::decode(SEED).unwrap().try_into().unwrap();
+        let (alg, _, jwk, encoding) = load_file(&ed25519(seed, "rfc", false)).unwrap();
+        assert_eq!(alg, Algorithm::EdDSA);
+        assert!(encoding.is_some());
+        // RFC 8037 A.3
+        assert_eq!(jwk.common.key_id.as_deref(), Some("kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"));
+
+        // public key ตัวเดียวกันได้ kid เดียวกัน แต่เซ็นไม่ได้
+        let (_, _, public, encoding) = load_file(&ed25519(seed, "rfc-pub", true)).unwrap();
+        assert_eq!(public.common.key_id, jwk.common.key_id);
+        assert!(encoding.is_none());
+    }
+
+    #[test]
+    fn build_keeps_old_keys_for_verify() {
+        let current = ed25519([1; 32], "current", false);
+        let old = ed25519([2; 32], "old", true);
+        let store = build(&current, &[old, current.clone()]).unwrap();
+
+        let kid = &store.signing.as_ref().unwrap().kid;
+        assert_eq!(store.verifying.len(), 2);
+        assert!(store.verifying.contains_key(kid));
+        // key ที่ซ้ำกับ signing key ไม่โผล่ใน JWKS สองรอบ
+        assert_eq!(store.jwks.keys.len(), 2);
+    }
+
+    #[test]
+    fn signing_key_must_be_private() {
+        assert!(build(&ed25519([3; 32], "pub-only", true), &[]).is_err());
+        assert!(build(&write("junk", "not a key"), &[]).is_err());
+        assert!(build("", &[]).unwrap().signing.is_none());
+    }
+}
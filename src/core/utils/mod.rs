@@ -1,3 +1,9 @@
 pub mod password;
+pub mod password_policy;
 pub mod jwt;
-pub mod token_hash;
\ No newline at end of file
+pub mod jwt_keys;
+pub mod token_hash;
+pub mod revocation;
+pub mod id_token;
+pub mod pat;
+pub mod rbac;
@@ -1,27 +1,159 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
 use axum::http::StatusCode;
-use bcrypt::{hash, verify, DEFAULT_COST};
 
+use crate::config::env::{Env, ENV};
 use crate::core::errors::AppError;
 
-// เก็บไว้เผื่อใช้ภายหลัง แต่ไม่ให้เตือน
-#[allow(dead_code)]
-pub fn hash_password(password: &str) -> Result<String, AppError> {
-    hash(password, DEFAULT_COST).map_err(|_| {
-        AppError::new(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "HASH_ERROR",
-            "Password hash error",
-        )
-    })
+fn hash_error() -> AppError {
+    AppError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "HASH_ERROR",
+        "Password hash error",
+    )
+}
+
+fn argon2(env: &Env) -> Result<Argon2<'static>, AppError> {
+    let params = Params::new(env.argon2_memory_kib, env.argon2_iterations, env.argon2_parallelism, None)
+        .map_err(|_| hash_error())?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+fn is_bcrypt(password_hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2y$"].iter().any(|p| password_hash.starts_with(p))
+}
+
+/// hash ด้วย algorithm ที่ตั้งไว้ (PASSWORD_HASH_ALGORITHM: argon2id | bcrypt)
+///
+/// argon2id/bcrypt กิน CPU หลายสิบ ms -> รันใน blocking thread ไม่ให้ request อื่นค้างตอน login เยอะ ๆ
+pub async fn hash_password(password: &str) -> Result<String, AppError> {
+    let password = password.to_owned();
+    tokio::task::spawn_blocking(move || hash_blocking(&password))
+        .await
+        .map_err(|_| hash_error())?
+}
+
+/// ดูรูปแบบ hash ที่เก็บไว้แล้วตรวจด้วย algorithm นั้น (ค่า parameter อ่านจากตัว hash)
+pub async fn verify_password(password: &str, password_hash: &str) -> Result<bool, AppError> {
+    let (password, password_hash) = (password.to_owned(), password_hash.to_owned());
+    tokio::task::spawn_blocking(move || verify_blocking(&password, &password_hash))
+        .await
+        .map_err(|_| hash_error())?
+}
+
+fn hash_blocking(password: &str) -> Result<String, AppError> {
+    let env = ENV.get().expect("ENV not initialized");
+    if env.password_hash_algorithm == "bcrypt" {
+        return bcrypt::hash(password, env.bcrypt_cost).map_err(|_| hash_error());
+    }
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|_| hash_error())?;
+    argon2(env)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|_| hash_error())
 }
 
-#[allow(dead_code)]
-pub fn verify_password(password: &str, password_hash: &str) -> Result<bool, AppError> {
-    verify(password, password_hash).map_err(|_| {
+fn verify_blocking(password: &str, password_hash: &str) -> Result<bool, AppError> {
+    let verify_error = || {
         AppError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             "VERIFY_ERROR",
             "Password verify error",
         )
-    })
+    };
+
+    if password_hash.starts_with("$argon2") {
+        let parsed = PasswordHash::new(password_hash).map_err(|_| verify_error())?;
+        return Ok(Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok());
+    }
+    if is_bcrypt(password_hash) {
+        return bcrypt::verify(password, password_hash).map_err(|_| verify_error());
+    }
+    Err(verify_error())
+}
+
+/// hash นี้เก่ากว่าที่ตั้งไว้ไหม (algorithm หรือ parameter ไม่ตรง) -> ควร hash ใหม่ตอน login สำเร็จ
+pub fn needs_rehash(password_hash: &str) -> bool {
+    let env = ENV.get().expect("ENV not initialized");
+    let bcrypt_cost = (env.password_hash_algorithm == "bcrypt").then_some(env.bcrypt_cost);
+    let Ok(params) = Params::new(env.argon2_memory_kib, env.argon2_iterations, env.argon2_parallelism, None) else {
+        return false;
+    };
+    outdated(password_hash, bcrypt_cost, &params)
+}
+
+/// bcrypt_cost = Some เมื่อ algorithm ที่ตั้งไว้คือ bcrypt, ไม่งั้นเทียบกับ argon2id ตาม params
+fn outdated(password_hash: &str, bcrypt_cost: Option<u32>, target: &Params) -> bool {
+    if let Some(want) = bcrypt_cost {
+        // $2b$<cost>$...
+        let cost = password_hash.split('$').nth(2).and_then(|c| c.parse::<u32>().ok());
+        return !is_bcrypt(password_hash) || cost != Some(want);
+    }
+
+    let Ok(parsed) = PasswordHash::new(password_hash) else { return true };
+    if parsed.algorithm.as_str() != "argon2id" || parsed.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+    let Ok(params) = Params::try_from(&parsed) else { return true };
+    params.m_cost() != target.m_cost() || params.t_cost() != target.t_cost() || params.p_cost() != target.p_cost()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ค่าต่ำ ๆ ให้เทสต์เร็ว
+    fn params(m: u32, t: u32) -> Params {
+        Params::new(m, t, 1, None).unwrap()
+    }
+
+    fn argon2_hash(password: &str, p: &Params) -> String {
+        let salt = SaltString::encode_b64(&[7u8; 16]).unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, p.clone())
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn verify_argon2_and_bcrypt() {
+        let a = argon2_hash("Secret123!", &params(64, 1));
+        assert!(verify_blocking("Secret123!", &a).unwrap());
+        assert!(!verify_blocking("secret123!", &a).unwrap());
+
+        let b = bcrypt::hash("Secret123!", 4).unwrap();
+        assert!(verify_blocking("Secret123!", &b).unwrap());
+        assert!(!verify_blocking("nope", &b).unwrap());
+
+        assert!(verify_blocking("x", "plaintext").is_err());
+    }
+
+    #[test]
+    fn rehash_when_argon2_params_change() {
+        let target = params(64, 1);
+        let h = argon2_hash("pw", &target);
+        assert!(!outdated(&h, None, &target));
+        assert!(outdated(&h, None, &params(128, 1)));
+        assert!(outdated(&h, None, &params(64, 2)));
+    }
+
+    #[test]
+    fn rehash_when_algorithm_changes() {
+        let target = params(64, 1);
+        let a = argon2_hash("pw", &target);
+        let b = bcrypt::hash("pw", 4).unwrap();
+
+        // ตั้งเป็น argon2id แต่เก็บเป็น bcrypt (และกลับกัน)
+        assert!(outdated(&b, None, &target));
+        assert!(outdated(&a, Some(4), &target));
+
+        assert!(!outdated(&b, Some(4), &target));
+        assert!(outdated(&b, Some(5), &target));
+    }
+
+    #[test]
+    fn rehash_unparseable_hash() {
+        assert!(outdated("garbage", None, &params(64, 1)));
+        assert!(outdated("$2b$xx$abc", Some(4), &params(64, 1)));
+    }
 }
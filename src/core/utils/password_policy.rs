@@ -0,0 +1,151 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::config::env::ENV;
+use crate::config::password_policy::PasswordPolicy;
+use crate::core::errors::AppError;
+
+static BREACHED: OnceLock<HashSet<String>> = OnceLock::new();
+
+/// โหลดไฟล์ครั้งแรกที่ใช้ (ตัวพิมพ์เล็ก, ข้ามบรรทัดว่างและ #)
+fn breached_list(path: &str) -> &'static HashSet<String> {
+    BREACHED.get_or_init(|| match std::fs::read_to_string(path) {
+        Ok(raw) => {
+            let set: HashSet<String> = raw
+                .lines()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(|l| l.to_lowercase())
+                .collect();
+            tracing::info!("🔐 Loaded {} breached passwords from {}", set.len(), path);
+            set
+        }
+        Err(e) => {
+            tracing::warn!("Cannot read breached password list {}: {}", path, e);
+            HashSet::new()
+        }
+    })
+}
+
+/// คืนรายการข้อที่ไม่ผ่าน (ว่าง = ผ่าน)
+fn violations(policy: &PasswordPolicy, password: &str, email: Option<&str>, username: Option<&str>) -> Vec<Value> {
+    let mut out = Vec::new();
+
+    if password.chars().count() < policy.min_length {
+        out.push(json!({ "rule": "min_length", "min": policy.min_length }));
+    }
+    if password.len() > policy.max_bytes {
+        out.push(json!({ "rule": "max_bytes", "max": policy.max_bytes }));
+    }
+    if policy.require_lower && !password.chars().any(|c| c.is_lowercase()) {
+        out.push(json!({ "rule": "require_lower" }));
+    }
+    if policy.require_upper && !password.chars().any(|c| c.is_uppercase()) {
+        out.push(json!({ "rule": "require_upper" }));
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        out.push(json!({ "rule": "require_digit" }));
+    }
+    if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+        out.push(json!({ "rule": "require_symbol" }));
+    }
+
+    // ห้ามมีอีเมล (ส่วนหน้า @) หรือ username อยู่ในรหัสผ่าน (สั้นกว่า 3 ตัวไม่นับ กันชนมั่ว)
+    let lower = password.to_lowercase();
+    let contains = |part: &str| {
+        let part = part.trim().to_lowercase();
+        part.chars().count() >= 3 && lower.contains(&part)
+    };
+    if email.and_then(|e| e.split('@').next()).is_some_and(contains) {
+        out.push(json!({ "rule": "contains_email" }));
+    }
+    if username.is_some_and(contains) {
+        out.push(json!({ "rule": "contains_username" }));
+    }
+
+    if let Some(path) = &policy.breached_list_file
+        && breached_list(path).contains(&lower)
+    {
+        out.push(json!({ "rule": "breached" }));
+    }
+
+    out
+}
+
+/// ตรวจรหัสผ่านใหม่ตาม PASSWORD_* ไม่ผ่าน = 400 WEAK_PASSWORD พร้อม details.violations
+pub fn check(password: &str, email: Option<&str>, username: Option<&str>) -> Result<(), AppError> {
+    let env = ENV.get().expect("ENV not initialized");
+    let found = violations(&env.password_policy, password, email, username);
+    if found.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::new(StatusCode::BAD_REQUEST, "WEAK_PASSWORD", "Password does not meet the password policy")
+        .with_details(json!({ "violations": found })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 10,
+            max_bytes: 72,
+            require_lower: true,
+            require_upper: true,
+            require_digit: true,
+            require_symbol: true,
+            breached_list_file: None,
+        }
+    }
+
+    fn rules(found: &[Value]) -> Vec<&str> {
+        found.iter().filter_map(|v| v["rule"].as_str()).collect()
+    }
+
+    #[test]
+    fn strong_password_passes() {
+        assert!(violations(&strict(), "Correct-Horse9", None, None).is_empty());
+    }
+
+    #[test]
+    fn reports_every_failed_rule() {
+        let found = violations(&strict(), "abc", None, None);
+        assert_eq!(rules(&found), ["min_length", "require_upper", "require_digit", "require_symbol"]);
+        assert_eq!(found[0]["min"], 10);
+    }
+
+    #[test]
+    fn length_counts_chars_but_max_counts_bytes() {
+        let policy = PasswordPolicy { max_bytes: 20, ..strict() };
+        // ภาษาไทย 1 ตัว = 3 byte: 10 ตัวผ่าน min_length แต่เกิน 20 byte
+        let found = violations(&policy, "กขคงจฉชซฌญ", None, None);
+        assert_eq!(rules(&found), ["max_bytes", "require_lower", "require_upper", "require_digit", "require_symbol"]);
+    }
+
+    #[test]
+    fn rejects_email_and_username() {
+        let found = violations(&strict(), "Somchai-2024!", Some("SomChai@example.com"), Some("chai2"));
+        assert_eq!(rules(&found), ["contains_email"]);
+
+        let found = violations(&strict(), "Xx-chai2024!", Some("s@example.com"), Some("chai2"));
+        assert_eq!(rules(&found), ["contains_username"]);
+
+        // สั้นกว่า 3 ตัวไม่นับ
+        assert!(violations(&strict(), "Correct-Horse9", Some("co@example.com"), Some("Ho")).is_empty());
+    }
+
+    // BREACHED โหลดครั้งเดียวต่อ process -> มีเทสต์เดียวที่ใช้ไฟล์
+    #[test]
+    fn rejects_breached_case_insensitive() {
+        let path = std::env::temp_dir().join(format!("breached-{}.txt", std::process::id()));
+        std::fs::write(&path, "# comment\n\nP@ssw0rd-123\n").unwrap();
+        let policy = PasswordPolicy { breached_list_file: Some(path.to_string_lossy().into()), ..strict() };
+
+        assert_eq!(rules(&violations(&policy, "p@SSW0RD-123", None, None)), ["breached"]);
+        assert!(violations(&policy, "Correct-Horse9", None, None).is_empty());
+        std::fs::remove_file(path).ok();
+    }
+}
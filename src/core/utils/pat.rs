@@ -0,0 +1,112 @@
+use axum::http::Method;
+use sqlx::Row;
+
+use crate::config::db::DB;
+use crate::core::errors::AppError;
+use crate::core::utils::token_hash;
+
+/// personal access token ขึ้นต้นด้วย prefix นี้ (แยกจาก JWT ได้ทันทีใน mw_jwt_auth)
+pub const PREFIX: &str = "pat_";
+
+// read = GET/HEAD เท่านั้น, write = ทุก method, admin = ใช้ permission ของ role ได้ (role อื่นที่ไม่ใช่ user)
+pub const SCOPES: &[&str] = &["read", "write", "admin"];
+
+/// user ที่ได้จาก personal access token
+pub struct PatUser {
+    pub id: i32,
+    pub user_id: i32,
+    pub email: String,
+    pub role: String,
+    pub scopes: Vec<String>,
+    pub exp: usize,
+}
+
+pub fn is_pat(token: &str) -> bool {
+    token.starts_with(PREFIX)
+}
+
+/// scope พอสำหรับ method นี้ไหม (read = GET/HEAD/OPTIONS, อย่างอื่นต้องมี write)
+pub fn allows(scopes: &[String], method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) || scopes.iter().any(|s| s == "write")
+}
+
+/// สุ่ม token ใหม่ คืน (token ตัวจริง, ส่วนหัวไว้โชว์ในรายการ)
+pub fn generate() -> (String, String) {
+    let token = format!("{}{}", PREFIX, token_hash::create_random_token());
+    let display = token[..PREFIX.len() + 8].to_string();
+    (token, display)
+}
+
+/// หา token ที่ยังใช้ได้ (ไม่ถูก revoke ไม่หมดอายุ) พร้อมอัปเดต last_used_at
+/// token ที่ไม่มี scope admin จะได้ role เป็น user (ไม่มี permission ของ role จริง)
+pub async fn resolve(db: &DB, token: &str) -> Result<Option<PatUser>, AppError> {
+    let row = sqlx::query(
+        r#"
+        WITH t AS (
+            UPDATE personal_access_tokens SET last_used_at = NOW()
+            WHERE token_hash = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            RETURNING id, user_id, scopes, expires_at
+        )
+        SELECT t.id, t.user_id, t.scopes, FLOOR(EXTRACT(EPOCH FROM t.expires_at))::BIGINT AS exp, u.email, u.role
+        FROM t JOIN users u ON u.id = t.user_id
+        "#,
+    )
+    .bind(token_hash::hash_token(token))
+    .fetch_optional(&db.pool)
+    .await?;
+
+    Ok(row.map(|r| {
+        let scopes: Vec<String> = r.get("scopes");
+        let mut role: String = r.get("role");
+        if role != "user" && !scopes.iter().any(|s| s == "admin") {
+            role = "user".to_string();
+        }
+        PatUser {
+            id: r.get("id"),
+            user_id: r.get("user_id"),
+            email: r.get("email"),
+            role,
+            scopes,
+            exp: r.get::<i64, _>("exp") as usize,
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scopes(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn read_scope_is_read_only() {
+        let read = scopes(&["read"]);
+        assert!(allows(&read, &Method::GET));
+        assert!(allows(&read, &Method::HEAD));
+        assert!(allows(&read, &Method::OPTIONS));
+        assert!(!allows(&read, &Method::POST));
+        assert!(!allows(&read, &Method::PATCH));
+        assert!(!allows(&read, &Method::DELETE));
+    }
+
+    #[test]
+    fn write_scope_allows_every_method() {
+        let write = scopes(&["read", "write"]);
+        assert!(allows(&write, &Method::POST));
+        assert!(allows(&write, &Method::PUT));
+        assert!(allows(&write, &Method::DELETE));
+        // admin อย่างเดียวไม่ได้แปลว่าเขียนได้
+        assert!(!allows(&scopes(&["admin"]), &Method::POST));
+    }
+
+    #[test]
+    fn generated_tokens_are_pats() {
+        let (token, display) = generate();
+        assert!(is_pat(&token));
+        assert!(token.starts_with(&display));
+        assert_eq!(display.len(), PREFIX.len() + 8);
+        assert!(!is_pat("eyJhbGciOiJIUzI1NiJ9.e30.x"));
+    }
+}
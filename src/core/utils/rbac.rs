@@ -0,0 +1,103 @@
+use crate::config::db::DB;
+use crate::core::errors::AppError;
+
+/// role นี้มี permission นี้ไหม (ดู role_permissions ใน db.sql ข้อ 18)
+pub async fn has_permission(db: &DB, role: &str, permission: &str) -> Result<bool, AppError> {
+    let ok: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM role_permissions WHERE role_name = $1 AND permission = $2)",
+    )
+    .bind(role)
+    .bind(permission)
+    .fetch_one(&db.pool)
+    .await?;
+    Ok(ok)
+}
+
+/// permission ใน `wanted` ที่ role นี้ไม่มี (ว่าง = มีครบ)
+pub async fn missing_permissions(db: &DB, role: &str, wanted: &[String]) -> Result<Vec<String>, AppError> {
+    let missing: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT p FROM UNNEST($2::TEXT[]) AS p
+        WHERE NOT EXISTS (SELECT 1 FROM role_permissions WHERE role_name = $1 AND permission = p)
+        ORDER BY p
+        "#,
+    )
+    .bind(role)
+    .bind(wanted)
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(missing)
+}
+
+/// permission ของ `role` ที่ `holder` ไม่มี (ว่าง = holder มีครบ จัดการ / ให้ role นี้ได้โดยไม่ได้สิทธิ์เพิ่ม)
+pub async fn missing_from(db: &DB, holder: &str, role: &str) -> Result<Vec<String>, AppError> {
+    let missing: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT t.permission FROM role_permissions t
+        WHERE t.role_name = $2
+          AND NOT EXISTS (SELECT 1 FROM role_permissions h WHERE h.role_name = $1 AND h.permission = t.permission)
+        ORDER BY t.permission
+        "#,
+    )
+    .bind(holder)
+    .bind(role)
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(missing)
+}
+
+/// error เวลาพยายามให้ / จัดการสิทธิ์ที่ตัวเองไม่มี
+pub fn escalation(missing: Vec<String>) -> AppError {
+    AppError::forbidden("ROLE_ESCALATION", "You cannot grant or manage permissions you do not have")
+        .with_details(serde_json::json!({ "missing": missing }))
+}
+
+// ต้องมี Postgres ที่รัน db.sql แล้ว: DATABASE_URL=... cargo test -- --ignored
+// ใช้เฉพาะ role ที่ seed ไว้ (admin = ทุกอย่าง, editor = homepage/carousel, user = ไม่มี) ไม่เขียนอะไรลง DB
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn db() -> DB {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        DB::connect(&url).await.unwrap()
+    }
+
+    fn perms(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn has_permission_follows_seeded_roles() {
+        let db = db().await;
+        assert!(has_permission(&db, "admin", "roles:manage").await.unwrap());
+        assert!(has_permission(&db, "editor", "homepage:write").await.unwrap());
+        assert!(!has_permission(&db, "editor", "users:manage").await.unwrap());
+        assert!(!has_permission(&db, "user", "homepage:write").await.unwrap());
+        assert!(!has_permission(&db, "no-such-role", "homepage:write").await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn missing_permissions_lists_what_role_lacks() {
+        let db = db().await;
+        let wanted = perms(&["users:manage", "carousel:write", "homepage:write"]);
+        assert_eq!(missing_permissions(&db, "editor", &wanted).await.unwrap(), perms(&["users:manage"]));
+        assert!(missing_permissions(&db, "admin", &wanted).await.unwrap().is_empty());
+        assert!(missing_permissions(&db, "user", &[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn missing_from_compares_roles() {
+        let db = db().await;
+        assert!(missing_from(&db, "admin", "editor").await.unwrap().is_empty());
+        assert!(missing_from(&db, "editor", "user").await.unwrap().is_empty());
+        assert_eq!(
+            missing_from(&db, "user", "editor").await.unwrap(),
+            perms(&["carousel:write", "homepage:write"])
+        );
+        assert!(missing_from(&db, "editor", "admin").await.unwrap().contains(&"roles:manage".to_string()));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::config::db::DB;
+use crate::config::env::ENV;
+use crate::core::errors::AppError;
+use crate::core::utils::jwt::{self, Claims};
+
+/// revoke token ใบเดียว (เช่น logout) เก็บไว้จนกว่า token จะหมดอายุเอง
+pub async fn revoke_token(db: &DB, claims: &Claims, reason: &str) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO revoked_tokens (jti, user_id, reason, expires_at)
+        VALUES ($1, $2, $3, to_timestamp($4))
+        ON CONFLICT (jti) DO NOTHING
+        "#,
+    )
+    .bind(&claims.jti)
+    .bind(claims.sub)
+    .bind(reason)
+    .bind(claims.exp as f64)
+    .execute(&db.pool)
+    .await?;
+    Ok(())
+}
+
+/// revoke ทุก token ของ user ที่ออกก่อนตอนนี้ (reset password / admin kick)
+/// และปิด refresh token, session, personal access token ที่ยังใช้งานได้ทั้งหมดด้วย
+pub async fn revoke_user(db: &DB, user_id: i32, reason: &str) -> Result<(), AppError> {
+    let env = ENV.get().expect("ENV not initialized");
+    let mut tx = db.pool.begin().await?;
+
+    // jti = NULL คือ entry ระดับ user, อยู่นานเท่าอายุ access token ก็พอ
+    sqlx::query(
+        r#"
+        INSERT INTO revoked_tokens (jti, user_id, reason, expires_at)
+        VALUES (NULL, $1, $2, NOW() + make_interval(secs => $3))
+        "#,
+    )
+    .bind(user_id)
+    .bind(reason)
+    .bind(jwt::access_ttl(env) as f64)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = $2 WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+    // session สวมรอยที่ถูกปิด (ทางไหนก็ตาม) ต้องจบใน audit log ด้วย
+    sqlx::query(
+        r#"
+        WITH s AS (
+            UPDATE sessions SET revoked_at = NOW(), revoked_reason = $2
+            WHERE user_id = $1 AND revoked_at IS NULL
+            RETURNING id, impersonator_id
+        )
+        UPDATE impersonations SET ended_at = NOW()
+        WHERE ended_at IS NULL AND session_id IN (SELECT id FROM s WHERE impersonator_id IS NOT NULL)
+        "#,
+    )
+        .bind(user_id)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE personal_access_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// ปิด session ของ refresh token family นี้ (logout / เจอ token เก่าถูกใช้ซ้ำ)
+pub async fn revoke_family(db: &DB, family_id: &str, reason: &str) -> Result<(), AppError> {
+    let mut tx = db.pool.begin().await?;
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = $2 WHERE family_id = $1 AND revoked_at IS NULL")
+        .bind(family_id)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+    // session สวมรอยที่ถูกปิด (ทางไหนก็ตาม) ต้องจบใน audit log ด้วย
+    sqlx::query(
+        r#"
+        WITH s AS (
+            UPDATE sessions SET revoked_at = NOW(), revoked_reason = $2
+            WHERE family_id = $1 AND revoked_at IS NULL
+            RETURNING id, impersonator_id
+        )
+        UPDATE impersonations SET ended_at = NOW()
+        WHERE ended_at IS NULL AND session_id IN (SELECT id FROM s WHERE impersonator_id IS NOT NULL)
+        "#,
+    )
+        .bind(family_id)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// ปิด session เดียวของ user (refresh token ของ session นั้นใช้ไม่ได้อีก access token ถูกปฏิเสธทันที)
+/// คืน false ถ้าไม่มี session นี้ หรือถูกปิดไปแล้ว
+pub async fn revoke_session(db: &DB, user_id: i32, session_id: i32, reason: &str) -> Result<bool, AppError> {
+    let family_id: Option<String> = sqlx::query_scalar(
+        "SELECT family_id FROM sessions WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(&db.pool)
+    .await?;
+
+    let Some(family_id) = family_id else { return Ok(false) };
+    revoke_family(db, &family_id, reason).await?;
+    Ok(true)
+}
+
+/// session ของ token ถูกปิดไปแล้วหรือยัง (ใช้ใน mw_jwt_auth) ไม่เจอ session = ถือว่าปิดแล้ว
+/// อัปเดต last_seen_at ไปด้วย (ไม่เกินนาทีละครั้ง)
+pub async fn is_session_revoked(db: &DB, session_id: i32) -> Result<bool, AppError> {
+    let active: bool = sqlx::query_scalar(
+        r#"
+        WITH touched AS (
+            UPDATE sessions SET last_seen_at = NOW()
+            WHERE id = $1 AND revoked_at IS NULL AND last_seen_at < NOW() - INTERVAL '1 minute'
+        )
+        SELECT EXISTS (SELECT 1 FROM sessions WHERE id = $1 AND revoked_at IS NULL)
+        "#,
+    )
+    .bind(session_id)
+    .fetch_one(&db.pool)
+    .await?;
+    Ok(!active)
+}
+
+/// เช็คว่า token ถูก revoke หรือยัง (ใช้ใน mw_jwt_auth)
+/// token ที่ออกในวินาทีเดียวกับตอน revoke ระดับ user ยังใช้ได้ เพื่อให้ออก token ใหม่ทันทีหลัง revoke ได้
+pub async fn is_revoked(db: &DB, claims: &Claims) -> Result<bool, AppError> {
+    let revoked: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM revoked_tokens
+            WHERE expires_at > NOW()
+              AND (
+                jti = $1
+                OR (jti IS NULL AND user_id = $2 AND $3 < FLOOR(EXTRACT(EPOCH FROM revoked_at)))
+              )
+        )
+        "#,
+    )
+    .bind(&claims.jti)
+    .bind(claims.sub)
+    .bind(claims.iat as f64)
+    .fetch_one(&db.pool)
+    .await?;
+    Ok(revoked)
+}
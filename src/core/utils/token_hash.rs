@@ -1,4 +1,3 @@
-#![allow(dead_code)]
 use rand::{thread_rng, Rng};
 use sha2::{Digest, Sha256};
 
@@ -13,4 +12,39 @@ pub fn hash_token(token: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
     hex::encode(hasher.finalize())
-}
\ No newline at end of file
+}
+
+/// เทียบ hash แบบใช้เวลาเท่ากันเสมอ (ไม่หยุดที่ตัวแรกที่ต่าง) กันเดาจากเวลาตอบ
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_time_eq_matches_plain_eq() {
+        let h = hash_token("abc");
+        assert!(constant_time_eq(&h, &hash_token("abc")));
+        assert!(!constant_time_eq(&h, &hash_token("abd")));
+        assert!(!constant_time_eq("abc", "abcd"));
+        assert!(constant_time_eq("", ""));
+    }
+
+    #[test]
+    fn hash_token_is_sha256_hex() {
+        assert_eq!(hash_token(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hash_token("abc").len(), 64);
+    }
+
+    #[test]
+    fn random_tokens_differ() {
+        let (a, b) = (create_random_token(), create_random_token());
+        assert_eq!(a.len(), 64);
+        assert_ne!(a, b);
+    }
+}
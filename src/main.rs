@@ -27,18 +27,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    // 4. Setup Router
+    // 4. Mailer (smtp / outbox)
+    if let Err(e) = core::mail::init(&env) {
+        tracing::error!("🔥 Failed to init mailer: {}", e);
+        std::process::exit(1);
+    }
+
+    // 5. JWT signing keys (RS256/EdDSA ถ้าตั้งไว้)
+    if let Err(e) = core::utils::jwt_keys::init(&env) {
+        tracing::error!("🔥 Failed to load JWT keys: {}", e);
+        std::process::exit(1);
+    }
+
+    // 6. Background Jobs (purge expired tokens)
+    core::jobs::spawn(db.clone());
+
+    // 7. Setup Router
     let app = api::router(db, env.clone())
         .layer(TraceLayer::new_for_http());
 
-    // 5. Server Setup
+    // 8. Server Setup
     let addr = SocketAddr::from(([0, 0, 0, 0], env.port));
     let listener = TcpListener::bind(addr).await?;
     
     tracing::info!("🚀 Server running on http://{}", addr);
 
-    // 6. Run Server with Graceful Shutdown
-    axum::serve(listener, app)
+    // 9. Run Server with Graceful Shutdown
+    // ต้องมี ConnectInfo ไม่งั้น GovernorLayer (rate limit ตาม IP) ดึง IP ไม่ได้
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 